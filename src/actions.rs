@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use std::collections::HashSet;
 
 impl Object {
     pub fn try_move(&mut self, dest: Point, map: &Map) {
@@ -11,6 +12,10 @@ impl Object {
                 if let Object { viewshed: Some(view), .. } = self {
                     view.refresh = true
                 }
+                //Rough ground takes longer to get across
+                if let Object { initiative: Some(init), .. } = self {
+                    init.action_cost = map.tiles[map.point2d_to_index(dest)].move_cost().unwrap_or(1.0);
+                }
             }
         }
         else {
@@ -18,7 +23,7 @@ impl Object {
         }
     }
 
-    pub fn try_attack(&mut self, target: &mut Object, target_id: usize, rng: &mut RandomNumberGenerator, logs: &mut LogBuffer) {
+    pub fn try_attack(&mut self, target: &mut Object, rng: &mut RandomNumberGenerator, events: &mut EventQueue) {
         for member in self.members.iter_mut() {
             if member.attack.is_able() {
                 let mut attack_target = 0;
@@ -34,14 +39,13 @@ impl Object {
 
                 let damage = member.attack.roll_for_damage(rng);
                 member.threat.add_threat(damage as u32);
-                self.inc_attacks.push(TargetedAttack::new((target_id, attack_target), damage));
+                self.inc_attacks.push(TargetedAttack::new((target.id, attack_target), damage));
 
-                logs.update_logs(LogMessage::new()
-                    .add_part(format!("{}", member.name), ColorPair::new(member.icon.get_render().1.fg, GREY10))
-                    .add_part("attacks", ColorPair::new(WHITE, GREY10))
-                    .add_part(format!("{}", target.members[attack_target].name), ColorPair::new(target.members[attack_target].icon.get_render().1.fg, GREY10))
-                    .add_part(format!("for {} damage.", damage), ColorPair::new(WHITE, GREY10))
-                );
+                events.push(GameEvent::DamageDealt {
+                    attacker: Combatant::from_member(member, self.kind),
+                    target: Combatant::from_member(&target.members[attack_target], target.kind),
+                    amount: damage
+                });
             }
             else {
                 member.attack.enable_attack();
@@ -52,357 +56,284 @@ impl Object {
 
 
 //Abilities
-#[derive(Clone, Copy, PartialEq)]
-pub enum Ability {
-    Taunt, CureWounds, LesserCureWounds, RallyingCry, KillShot, Deforest, Block, MagicMissile, LesserMagicMissile, PsyBolt, Cleave
+//An ability as written in the abilities file: who it can be aimed at, and what it does once it lands
+#[derive(Clone, Deserialize)]
+pub struct AbilityDef {
+    pub name: String,
+    pub cooldown: i32,
+    #[serde(default)]
+    pub description: String,
+    //What the log says after the caster's name, e.g. "fires a deadly shot at"
+    #[serde(default)]
+    pub message: Option<String>,
+    pub target: AbilityTarget,
+    #[serde(default)]
+    pub range: AbilityRange,
+    pub effects: Vec<AbilityEffect>,
+    //Threat the caster gains for using it, and again for every enemy member it damages
+    #[serde(default)]
+    pub threat: u32,
+    #[serde(default)]
+    pub threat_per_hit: u32,
+    #[serde(default)]
+    pub ends_attack: bool
+}
+
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+pub enum AbilityTarget {
+    Caster, Allies(MemberPick), Enemy(MemberPick), Terrain
+}
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+pub enum MemberPick {
+    MostInjured, Random, All
+}
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+pub enum AbilityRange {
+    Any, Melee
+}
+impl Default for AbilityRange {
+    fn default() -> Self { AbilityRange::Any }
+}
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+pub enum AbilityEffect {
+    //Damage dice as (number, sides)
+    Damage(i32, i32),
+    //Damage equal to one of the caster's regular attacks
+    CasterAttack,
+    Heal(i32, i32),
+    //A modifier and how many turns it lasts
+    Modifier(ModifierEffect, u32),
+    //Cuts down the trees right next to the caster
+    ClearTrees
+}
+
+//Everything the abilities file can get wrong on its own
+pub fn validate_abilities(abilities: &Vec<AbilityDef>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for ability in abilities.iter() {
+        if ability.name.trim().is_empty() {
+            return Err(String::from("an ability is missing its name"))
+        }
+        if !seen.insert(ability.name.as_str()) {
+            return Err(format!("the ability \"{}\" is defined more than once", ability.name))
+        }
+        if ability.cooldown < 0 {
+            return Err(format!("the ability \"{}\" has a negative cooldown", ability.name))
+        }
+        if ability.effects.is_empty() {
+            return Err(format!("the ability \"{}\" has no effects", ability.name))
+        }
+        for effect in ability.effects.iter() {
+            match effect {
+                AbilityEffect::Damage(n, d) | AbilityEffect::Heal(n, d) if *n <= 0 || *d <= 0 =>
+                    return Err(format!("the ability \"{}\" rolls {}d{}, both numbers must be at least 1", ability.name, n, d)),
+                AbilityEffect::Damage(..) | AbilityEffect::CasterAttack if !matches!(ability.target, AbilityTarget::Enemy(_)) =>
+                    return Err(format!("the ability \"{}\" deals damage, so it has to target an enemy", ability.name)),
+                AbilityEffect::ClearTrees if ability.target != AbilityTarget::Terrain =>
+                    return Err(format!("the ability \"{}\" clears trees, so its target has to be Terrain", ability.name)),
+                _ => {}
+            }
+        }
+        if ability.range == AbilityRange::Melee && !matches!(ability.target, AbilityTarget::Enemy(_)) {
+            return Err(format!("the ability \"{}\" needs melee range, which only makes sense against an enemy", ability.name))
+        }
+    }
+
+    return Ok(())
 }
+
+#[derive(Serialize, Deserialize)]
 pub struct StoredAbility {
-    pub ability: Ability,
     pub name: String,
     pub on_cooldown: bool,
-    pub source_obj: usize,
+    pub source_obj: EntityId,
     pub source_member: usize,
     pub source_ability_id: usize
 }
 impl StoredAbility {
-    pub fn new(ability: Ability, source_obj: usize, source_member: usize, source_ability_id: usize, on_cooldown: bool) -> StoredAbility {
-        let name = get_ability_name(ability);
+    pub fn new(name: String, source_obj: EntityId, source_member: usize, source_ability_id: usize, on_cooldown: bool) -> StoredAbility {
         StoredAbility {
-            ability, name, on_cooldown, source_obj, source_member, source_ability_id
+            name, on_cooldown, source_obj, source_member, source_ability_id
         }
     }
     pub fn is_on_cooldown(&self) -> bool { self.on_cooldown }
     pub fn set_source_on_cooldown(&mut self, objects: &mut Vec<Object>) {
-        let ability_source = &mut objects[self.source_obj].members[self.source_member].abilities[self.source_ability_id];
-        ability_source.set_on_cooldown();
-    }
-}
-
-pub fn get_ability_name(ability: Ability) -> String {
-    match ability {
-        Ability::Taunt => String::from("Taunt"),
-        Ability::Block => String::from("Block"),
-        Ability::CureWounds => String::from("Cure Wounds"),
-        Ability::LesserCureWounds => String::from("Lesser Cure"),
-        Ability::RallyingCry => String::from("Rallying Cry"),
-        Ability::KillShot => String::from("Kill Shot"),
-        Ability::Deforest => String::from("Deforest"),
-        Ability::MagicMissile => String::from("Magic Missile"),
-        Ability::LesserMagicMissile => String::from("Lesser Missile"),
-        Ability::Cleave => String::from("Cleave"),
-        Ability::PsyBolt => String::from("Psy-Bolt")
-    }
-}
-
-pub fn get_ability_cooldown(ability: Ability) -> i32 {
-    match ability {
-        Ability::Taunt => 15,
-        Ability::Block => 10,
-        Ability::CureWounds => 20,
-        Ability::LesserCureWounds => 15,
-        Ability::RallyingCry => 20,
-        Ability::KillShot => 30,
-        Ability::Deforest => 15,
-        Ability::MagicMissile => 10,
-        Ability::LesserMagicMissile => 20,
-        Ability::Cleave => 15,
-        Ability::PsyBolt => 3
+        if let Some(obj) = objects.get_by_id_mut(self.source_obj) {
+            obj.members[self.source_member].abilities[self.source_ability_id].set_on_cooldown();
+        }
     }
 }
 
-pub fn get_ability_description(ability: Ability) -> String {
-    match ability {
-        Ability::Taunt => String::from("Increases the amount of threat generated by this Elf for 5 turns."),
-        Ability::CureWounds => String::from("Heals the most injured party member for 3d4 hit points."),
-        Ability::LesserCureWounds => String::from("Heals the most injured party member for 2d3 hit points."),
-        Ability::RallyingCry => String::from("Gives all elves in the party +1 damage to their attacks for 5 turns."),
-        Ability::KillShot => String::from("A ranged shot that targets the most injured member of the target party, dealing 2d6 damage."),
-        Ability::Deforest => String::from("Chops down all trees directly adjacent to the party."),
-        Ability::Block => String::from("Blocks 5 damage from enemy attacks for the next 2 turns."),
-        Ability::MagicMissile | Ability::LesserMagicMissile => String::from("An arcane projectile that strikes a random member of a target for 1d3 damage."),
-        Ability::Cleave => String::from("Attacks each member of a target within melee range."),
-        _ => String::from("")
-    }
-}
+pub fn handle_abilities(objects: &mut Vec<Object>, map: &mut Map, data: &GameData, ability: &mut StoredAbility, rng: &mut RandomNumberGenerator, events: &mut EventQueue, target: Option<EntityId>) {
+    //Resolve the entity IDs to where those objects currently sit in the list
+    let source_obj = match objects.index_of(ability.source_obj) {
+        Some(idx) => idx,
+        None => return
+    };
+    let target = target.and_then(|id| objects.index_of(id));
+    let def = match data.ability(&ability.name) {
+        Some(def) => def,
+        None => return
+    };
 
-pub fn handle_abilities(objects: &mut Vec<Object>, map: &mut Map, ability: &mut StoredAbility, rng: &mut RandomNumberGenerator, logs: &mut LogBuffer, target: Option<usize>) {
     if !ability.is_on_cooldown() {
-        let success = match ability.ability {
-            Ability::Taunt => run_taunt(&mut objects[ability.source_obj].members[ability.source_member], logs),
-            Ability::Block => run_block(&mut objects[ability.source_obj].members[ability.source_member], logs),
-            Ability::CureWounds => run_cure_wounds(&mut objects[ability.source_obj].members, ability.source_member, rng, logs, false),
-            Ability::LesserCureWounds => run_cure_wounds(&mut objects[ability.source_obj].members, ability.source_member, rng, logs, true),
-            Ability::RallyingCry => run_rallying_cry(&mut objects[ability.source_obj].members, ability.source_member, logs),
-            Ability::KillShot => run_killshot(objects, target, (ability.source_obj, ability.source_member), logs, rng),
-            Ability::Deforest => run_deforest(objects[ability.source_obj].pos.as_ref().unwrap(), map),
-            Ability::MagicMissile | Ability::LesserMagicMissile => run_magic_missile(objects, target, (ability.source_obj, ability.source_member), logs, rng),
-            Ability::Cleave => run_cleave(objects, ability.source_obj, ability.source_member, target, logs, rng),
-            _ => false
-        };
-        if success { ability.set_source_on_cooldown(objects); }
+        match use_ability(objects, map, def, (source_obj, ability.source_member), target, rng, events) {
+            Ok(_) => ability.set_source_on_cooldown(objects),
+            Err(reason) => events.push(GameEvent::AbilityFailed { reason })
+        }
     }
     else {
-        logs.update_logs(LogMessage::new()
-            .add_part(format!("{}'s", objects[ability.source_obj].members[ability.source_member].name), ColorPair::new(objects[ability.source_obj].members[ability.source_member].icon.get_render().1.fg, GREY10))
-            .add_part(format!("{} is still on cooldown!", ability.name), ColorPair::new(WHITE, GREY10))
-        );
+        events.push(GameEvent::AbilityOnCooldown {
+            caster: Combatant::from_member(&objects[source_obj].members[ability.source_member], objects[source_obj].kind),
+            ability: ability.name.clone()
+        });
     }
-    if let Some(view) = &mut objects[ability.source_obj].viewshed { view.refresh = true; }
-}
-
-fn run_taunt(member: &mut PartyMember, logs: &mut LogBuffer) -> bool {
-    member.modifiers.push(Modifier::new(ModifierEffect::PlusThreat(5), 5, false));
-    member.threat.add_threat(15);
-    logs.update_logs(LogMessage::new()
-        .add_part(format!("{}",member.name), ColorPair::new(member.icon.get_render().1.fg, GREY10))
-        .add_part("lets out a threatening shout, taunting enemies to attack them!", ColorPair::new(WHITE, GREY10))
-    );
-    return true
-}
-fn run_block(member: &mut PartyMember, logs: &mut LogBuffer) -> bool {
-    member.modifiers.push(Modifier::new(ModifierEffect::Block(5), 2, false));
-    logs.update_logs(LogMessage::new()
-        .add_part(format!("{}",member.name), ColorPair::new(member.icon.get_render().1.fg, GREY10))
-        .add_part("raises their shield, blocking the enemies' blows!", ColorPair::new(WHITE, GREY10))
-    );
-    member.attack.disable_attack();
-    return true
+    if let Some(view) = &mut objects[source_obj].viewshed { view.refresh = true; }
 }
-fn run_rallying_cry(members: &mut Vec<PartyMember>, caster: usize, logs: &mut LogBuffer) -> bool {
-    for member in members.iter_mut() {
-        member.modifiers.push(Modifier::new(ModifierEffect::PlusAttack(1), 5, false));
-    }
 
-    logs.update_logs(LogMessage::new()
-        .add_part(format!("{}", members[caster].name), ColorPair::new(members[caster].icon.get_render().1.fg, GREY10))
-        .add_part("lets out a rallying cry, bolstering the party's morale!", ColorPair::new(WHITE, GREY10))
-    );
-    members[caster].threat.add_threat(5);
-
-    return true
-}
-fn run_cleave(objects: &mut Vec<Object>, source_obj: usize, source_member: usize, target_obj: Option<usize>, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> bool {
-    if target_obj.is_none() {
-        logs.update_logs(LogMessage::new()
-            .add_part("That ability needs a target!", ColorPair::new(WHITE, GREY10))
-        );
-        return false
-    }
-    {
-        let obj = &mut objects[target_obj.unwrap()];
-
-        if obj.tag != ActorTag::Enemy || obj.members.is_empty() {
-            logs.update_logs(LogMessage::new()
-                .add_part("Why would you want to do that?", ColorPair::new(WHITE, GREY10))
-            );
-            return false
+//Works out who an ability lands on, applies each of its effects in turn, and reports what happened.
+//Nothing is changed unless the ability can actually go off.
+pub fn use_ability(objects: &mut Vec<Object>, map: &mut Map, def: &AbilityDef, caster: (usize, usize), target: Option<usize>, rng: &mut RandomNumberGenerator, events: &mut EventQueue) -> Result<(), AbilityFailure> {
+    //The object the effects land on, which of its members, and who to name in the log
+    let (affected_obj, members, named) = match def.target {
+        AbilityTarget::Caster => (caster.0, vec![caster.1], None),
+        AbilityTarget::Terrain => (caster.0, Vec::new(), None),
+        AbilityTarget::Allies(pick) => {
+            let members = pick_members(&objects[caster.0].members, pick, rng);
+            let named = describe_pick(&objects[caster.0], pick, &members);
+            (caster.0, members, named)
+        },
+        AbilityTarget::Enemy(pick) => {
+            let tgt = target.ok_or(AbilityFailure::NeedsTarget)?;
+            if !is_hostile(objects[caster.0].tag, objects[tgt].tag) || objects[tgt].members.is_empty() {
+                return Err(AbilityFailure::InvalidTarget)
+            }
+            if def.range == AbilityRange::Melee {
+                let target_position = objects[tgt].pos.as_ref().unwrap().clone();
+                let adjacencies = objects[caster.0].pos.as_ref().unwrap().clone().get_neighbors();
+                if !adjacencies.contains(&target_position) {
+                    return Err(AbilityFailure::OutOfRange)
+                }
+            }
+            let members = pick_members(&objects[tgt].members, pick, rng);
+            let named = describe_pick(&objects[tgt], pick, &members);
+            (tgt, members, named)
         }
-    }
-
-    let target_position = objects[target_obj.unwrap()].pos.as_ref().unwrap().clone();
-    let adjacencies = objects[source_obj].pos.as_ref().unwrap().clone().get_neighbors();
-    if !adjacencies.contains(&target_position) {
-        logs.update_logs(LogMessage::new()
-            .add_part("Your target must be in melee range to use this ability!", ColorPair::new(WHITE, GREY10))
-        );
-        return false
-    }
-
-    let idxlist = {
-        let enemy_members = &mut objects[target_obj.unwrap()].members;
-        let mut vec = Vec::new();
-        for (i, _) in enemy_members.iter().enumerate() { vec.push(i); }
-        vec
     };
 
-    let player = &mut objects[source_obj];
-    let amt = player.members[source_member].attack.roll_for_damage(rng);
-    for idx in idxlist.iter() {
-        player.inc_attacks.push(TargetedAttack::new((target_obj.unwrap(), *idx), amt));
+    //Clearing trees is the only effect that can come up empty, so check it before anything else happens
+    let mut amount = None;
+    if def.effects.contains(&AbilityEffect::ClearTrees) {
+        let source_pos = objects[caster.0].pos.as_ref().unwrap().clone();
+        let trees = clear_trees(&source_pos, map);
+        if trees == 0 { return Err(AbilityFailure::NoTrees) }
+        amount = Some(trees);
     }
-    player.members[source_member].attack.disable_attack();
-    player.members[source_member].threat.add_threat(15 * idxlist.len() as u32);
-
-    logs.update_logs(LogMessage::new()
-        .add_part(format!("{}", player.members[source_member].name), ColorPair::new(player.members[source_member].icon.get_render().1.fg, GREY10))
-        .add_part("attacks each unit in", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", objects[target_obj.unwrap()].name.clone()), ColorPair::new(objects[target_obj.unwrap()].render.as_ref().unwrap().get_render().1.fg.clone(), GREY10))
-        .add_part("for", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", amt), ColorPair::new(GOLD, GREY10))
-        .add_part("damage.", ColorPair::new(WHITE, GREY10))
-    );
-
-    return true
-}
-
-fn run_cure_wounds(members: &mut Vec<PartyMember>, caster_id: usize, rng: &mut RandomNumberGenerator, logs: &mut LogBuffer, lesser: bool) -> bool {
-    let health_list = {
-        let mut vec = members.iter().enumerate()
-            .map(|(i, m)| (i, m.health.get_max() - m.health.get_life()))
-            .collect::<Vec<(usize, i32)>>();
-        vec.sort_by(|a,b| b.1.cmp(&a.1));
-        vec
-    };
-    let amt = if !lesser { rng.roll_dice(3,4) }
-        else{ rng.roll_dice(2, 3) };
-    members[health_list[0].0].health.gain_life(amt);
-
-    logs.update_logs(LogMessage::new()
-        .add_part(format!("{}", members[caster_id].name.clone()), ColorPair::new(members[caster_id].icon.get_render().1.fg, GREY10))
-        .add_part("casts a healing wave upon", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", members[health_list[0].0].name), ColorPair::new(members[health_list[0].0].icon.get_render().1.fg, GREY10))
-        .add_part("for", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", amt), ColorPair::new(GOLD, GREY10))
-        .add_part("HP.", ColorPair::new(WHITE, GREY10))
-    );
-
-    members[caster_id].attack.disable_attack();
-
-    return true
-}
-
-fn run_deforest(source_pos: &Point, map: &mut Map) -> bool {
-    let neighbor_list = {
-        let mut vec = Vec::new();
-        //let mut second_vec = Vec::new();
-
-        vec.append(&mut source_pos.get_neighbors());
-        /*
-        for p in vec.iter() {
-            second_vec.append(&mut p.get_neighbors());
-        }
-        vec.append(&mut second_vec);
-        vec.dedup();
-         */
-        vec.retain(|p| p.x >= 1 && p.y >= 1 && p.x <= map.width - 2 && p.y <= map.height - 2);
-
-        vec
-    };
-
-    let idx_list = neighbor_list.iter()
-        .map(|i| map.point2d_to_index(*i))
-        .collect::<Vec<usize>>();
 
-    let mut treecount = 0;
-    for idx in idx_list.into_iter() {
-        if map.tiles[idx] == TileClass::Tree {
-            map.tiles[idx] = TileClass::ForestFloor;
-            treecount += 1;
+    let mut healing = false;
+    for effect in def.effects.iter() {
+        match *effect {
+            AbilityEffect::Damage(n, d) => {
+                let amt = rng.roll_dice(n, d);
+                deal_damage(objects, caster.0, affected_obj, &members, amt);
+                amount = Some(amt);
+            },
+            AbilityEffect::CasterAttack => {
+                let amt = objects[caster.0].members[caster.1].attack.roll_for_damage(rng);
+                deal_damage(objects, caster.0, affected_obj, &members, amt);
+                amount = Some(amt);
+            },
+            AbilityEffect::Heal(n, d) => {
+                let amt = rng.roll_dice(n, d);
+                for m in members.iter() {
+                    objects[affected_obj].members[*m].health.gain_life(amt);
+                }
+                amount = Some(amt);
+                healing = true;
+            },
+            AbilityEffect::Modifier(effect, turns) => {
+                for m in members.iter() {
+                    objects[affected_obj].members[*m].modifiers.push(Modifier::new(effect, turns, false));
+                }
+            },
+            AbilityEffect::ClearTrees => {}
         }
     }
 
-    return if treecount > 0 { true } else { false }
+    let hits = if affected_obj != caster.0 { members.len() as u32 } else { 0 };
+    let caster_kind = objects[caster.0].kind;
+    let caster_member = &mut objects[caster.0].members[caster.1];
+    caster_member.threat.add_threat(def.threat + def.threat_per_hit * hits);
+    if def.ends_attack { caster_member.attack.disable_attack(); }
+
+    events.push(GameEvent::AbilityUsed {
+        caster: Combatant::from_member(caster_member, caster_kind),
+        ability: def.name.clone(),
+        target: named,
+        amount,
+        healing
+    });
+
+    return Ok(())
 }
 
-fn run_killshot(objects: &mut Vec<Object>, target: Option<usize>, source_ids: (usize, usize), logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> bool {
-    if target.is_none() {
-        logs.update_logs(LogMessage::new()
-            .add_part("That ability needs a target!", ColorPair::new(WHITE, GREY10))
-        );
-        return false
+//Player abilities go after enemies, and enemy abilities go after the player
+fn is_hostile(caster: ActorTag, target: ActorTag) -> bool {
+    match caster {
+        ActorTag::Player => target == ActorTag::Enemy,
+        ActorTag::Enemy => target == ActorTag::Player,
+        _ => false
     }
+}
 
-    let amt = rng.roll_dice(2, 6);
-    let mut idx = 0;
-    {
-        let obj = &mut objects[target.unwrap()];
-
-        if obj.tag != ActorTag::Enemy || obj.members.is_empty() {
-            logs.update_logs(LogMessage::new()
-                .add_part("Why would you want to do that?", ColorPair::new(WHITE, GREY10))
-            );
-            return false
+fn pick_members(members: &Vec<PartyMember>, pick: MemberPick, rng: &mut RandomNumberGenerator) -> Vec<usize> {
+    if members.is_empty() { return Vec::new() }
+    match pick {
+        MemberPick::All => (0..members.len()).collect(),
+        MemberPick::Random => vec![rng.range(0, members.len())],
+        MemberPick::MostInjured => {
+            let health_list = {
+                let mut vec = members.iter().enumerate()
+                    .map(|(i, m)| (i, m.health.get_max() - m.health.get_life()))
+                    .collect::<Vec<(usize, i32)>>();
+                vec.sort_by(|a, b| b.1.cmp(&a.1));
+                vec
+            };
+            vec![health_list[0].0]
         }
-
-        let health_list = {
-            let mut vec = obj.members.iter().enumerate()
-                .map(|(i, m)| (i, m.health.get_max() - m.health.get_life()))
-                .collect::<Vec<(usize, i32)>>();
-            vec.sort_by(|a, b| b.1.cmp(&a.1));
-            vec
-        };
-        idx = health_list[0].0;
     }
-    logs.update_logs(LogMessage::new()
-        .add_part(format!("{}", objects[source_ids.0].members[source_ids.1].name), ColorPair::new(objects[source_ids.0].members[source_ids.1].icon.get_render().1.fg, GREY10))
-        .add_part("fires a deadly shot at", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", objects[target.unwrap()].members[idx].name), ColorPair::new(objects[target.unwrap()].members[idx].icon.get_render().1.fg, GREY10))
-        .add_part("for", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", amt), ColorPair::new(GOLD, GREY10))
-        .add_part("damage.", ColorPair::new(WHITE, GREY10))
-    );
-
-    objects[source_ids.0].members[source_ids.1].attack.disable_attack();
-    objects[source_ids.0].members[source_ids.1].threat.add_threat(30);
-    objects[source_ids.0].inc_attacks.push(TargetedAttack::new((target.unwrap(), idx), amt));
-
-    return true
 }
 
-fn run_magic_missile(objects: &mut Vec<Object>, target: Option<usize>, source_ids: (usize, usize), logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> bool {
-    if target.is_none() {
-        logs.update_logs(LogMessage::new()
-            .add_part("That ability needs a target!", ColorPair::new(WHITE, GREY10))
-        );
-        return false
+//A single member gets named in the log, a whole party goes by the party's name
+fn describe_pick(obj: &Object, pick: MemberPick, members: &Vec<usize>) -> Option<Combatant> {
+    return match pick {
+        MemberPick::All => Some(Combatant::from_object(obj)),
+        _ => members.first().map(|m| Combatant::from_member(&obj.members[*m], obj.kind))
     }
-    {
-        let obj = &mut objects[target.unwrap()];
+}
 
-        if obj.tag != ActorTag::Enemy || obj.members.is_empty() {
-            logs.update_logs(LogMessage::new()
-                .add_part("Why would you want to do that?", ColorPair::new(WHITE, GREY10))
-            );
-            return false
-        }
+fn deal_damage(objects: &mut Vec<Object>, source_obj: usize, target_obj: usize, members: &Vec<usize>, amt: i32) {
+    let target_id = objects[target_obj].id;
+    for m in members.iter() {
+        objects[source_obj].inc_attacks.push(TargetedAttack::new((target_id, *m), amt));
     }
-
-    let idx = rng.range(0,objects[target.unwrap()].members.len());
-    let amt = rng.roll_dice(1, 3);
-
-    logs.update_logs(LogMessage::new()
-        .add_part(format!("{}", objects[source_ids.0].members[source_ids.1].name), ColorPair::new(objects[source_ids.0].members[source_ids.1].icon.get_render().1.fg, GREY10))
-        .add_part("casts an arcane missile toward", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", objects[target.unwrap()].members[idx].name), ColorPair::new(objects[target.unwrap()].members[idx].icon.get_render().1.fg, GREY10))
-        .add_part("for", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", amt), ColorPair::new(GOLD, GREY10))
-        .add_part("damage.", ColorPair::new(WHITE, GREY10))
-    );
-
-    objects[source_ids.0].members[source_ids.1].attack.disable_attack();
-    objects[source_ids.0].members[source_ids.1].threat.add_threat(5);
-    objects[source_ids.0].inc_attacks.push(TargetedAttack::new((target.unwrap(), idx), amt));
-
-    return true
 }
 
-pub fn run_psybolt(objects: &mut Vec<Object>, target: Option<usize>, source_ids: (usize, usize), logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) -> bool {
-    if target.is_none() {
-        return false
-    }
-    {
-        let obj = &mut objects[target.unwrap()];
+fn clear_trees(source_pos: &Point, map: &mut Map) -> i32 {
+    let neighbor_list = {
+        let mut vec = source_pos.get_neighbors();
+        vec.retain(|p| p.x >= 1 && p.y >= 1 && p.x <= map.width - 2 && p.y <= map.height - 2);
+        vec
+    };
 
-        if obj.tag != ActorTag::Player || obj.members.is_empty() {
-            return false
+    let mut treecount = 0;
+    for p in neighbor_list.iter() {
+        let idx = map.point2d_to_index(*p);
+        if map.tiles[idx] == TileClass::Tree || map.tiles[idx] == TileClass::Undergrowth {
+            map.tiles[idx] = TileClass::ForestFloor;
+            treecount += 1;
         }
     }
 
-    let idx = rng.range(0,objects[target.unwrap()].members.len());
-    let amt = rng.roll_dice(1, 3);
-
-    logs.update_logs(LogMessage::new()
-        .add_part(format!("{}", objects[source_ids.0].members[source_ids.1].name), ColorPair::new(objects[source_ids.0].members[source_ids.1].icon.get_render().1.fg, GREY10))
-        .add_part("casts a psychic bolt toward", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", objects[target.unwrap()].members[idx].name), ColorPair::new(objects[target.unwrap()].members[idx].icon.get_render().1.fg, GREY10))
-        .add_part("for", ColorPair::new(WHITE, GREY10))
-        .add_part(format!("{}", amt), ColorPair::new(GOLD, GREY10))
-        .add_part("damage.", ColorPair::new(WHITE, GREY10))
-    );
-
-    objects[source_ids.0].members[source_ids.1].attack.disable_attack();
-    objects[source_ids.0].members[source_ids.1].threat.add_threat(5);
-    objects[source_ids.0].inc_attacks.push(TargetedAttack::new((target.unwrap(), idx), amt));
-
-    return true
+    return treecount
 }
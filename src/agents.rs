@@ -0,0 +1,73 @@
+use crate::prelude::*;
+
+/// Something that can play the game on its own. It sees the world the same way the player's turn does,
+/// and answers with the action to take next.
+pub trait Agent {
+    fn act(&mut self, world: &World, targets: &TargetList) -> Actions;
+}
+
+/// Plays `game` with `agent` until the run is over or `max_steps` actions have been taken.
+/// Returns how many actions were taken. Not every action passes a turn, so this can be more than `game.turn`.
+pub fn run_agent<A: Agent + ?Sized>(game: &mut Game, agent: &mut A, max_steps: u32) -> u32 {
+    let mut steps = 0;
+    while !game.is_over() && steps < max_steps {
+        let action = agent.act(&game.world, &game.player_targets);
+        game.step(action);
+        steps += 1;
+    }
+    return steps
+}
+
+/// Mashes buttons: moves, waits and uses abilities at random. Useful for shaking out crashes.
+pub struct RandomAgent {
+    rng: RandomNumberGenerator
+}
+impl RandomAgent {
+    pub fn new(seed: u64) -> RandomAgent { RandomAgent { rng: RandomNumberGenerator::seeded(seed) } }
+}
+impl Agent for RandomAgent {
+    fn act(&mut self, world: &World, _targets: &TargetList) -> Actions {
+        const MOVES: [Actions; 10] = [
+            Actions::MoveUp, Actions::MoveDown, Actions::MoveLeft, Actions::MoveRight,
+            Actions::MoveUpLeft, Actions::MoveUpRight, Actions::MoveDownLeft, Actions::MoveDownRight,
+            Actions::Wait, Actions::CycleTarget
+        ];
+        let abilities: usize = world.objects[0].members.iter().map(|m| m.abilities.len()).sum();
+
+        let roll = self.rng.range(0, MOVES.len() + abilities);
+        return if roll < MOVES.len() {
+            MOVES[roll]
+        } else {
+            Actions::use_ability(roll - MOVES.len()).unwrap_or(Actions::Wait)
+        }
+    }
+}
+
+/// Heads straight for the portal, stopping to fight whatever gets next to it on the way. Never retreats upstairs.
+pub struct GreedyAgent;
+impl Agent for GreedyAgent {
+    fn act(&mut self, world: &World, _targets: &TargetList) -> Actions {
+        let player = &world.objects[0];
+        let pos = player.pos.unwrap();
+
+        //Bumping into an enemy attacks it
+        let neighbors = pos.get_neighbors();
+        for obj in world.objects.iter() {
+            if let Object { pos: Some(obj_pos), tag: ActorTag::Enemy, floor, .. } = obj {
+                if *floor == player.floor && neighbors.contains(obj_pos) {
+                    return Actions::step(*obj_pos - pos).unwrap_or(Actions::Wait)
+                }
+            }
+        }
+
+        let map = &world.map;
+        let path = a_star_search(map.point2d_to_index(pos), map.point2d_to_index(map.exit_pos), map);
+        if path.success && path.steps.len() > 1 && map.tiles[path.steps[1]] != TileClass::ReturnPortal {
+            let next = map.index_to_point2d(path.steps[1]);
+            return Actions::step(next - pos).unwrap_or(Actions::Wait)
+        }
+
+        //Boxed in for now, so let the enemies come to us
+        return Actions::Wait
+    }
+}
@@ -1,14 +1,16 @@
 use crate::prelude::*;
 
+#[derive(Serialize, Deserialize)]
 pub enum AIState {
     Idle,
     Chasing,
     Hunting
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct AIClass {
     pub state: AIState,
-    pub target: Option<usize>,
+    pub target: Option<EntityId>,
     pub tgt_memory: u16,
     pub tgt_heatmap: HeatMap
 }
@@ -23,6 +25,7 @@ impl AIClass {
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct HeatMap {
     pub lifetime: u16,
     pub nodes: Vec<Point>,
@@ -85,7 +88,7 @@ impl HeatMap {
     //Finds the shortest path to the nearest hot node in the heatmap
     pub fn get_closest_heat(&self, map: &Map, start: Point) -> Point {
         let targets = nodes_to_map_targets(&self.nodes, &self.old_nodes, map);
-        let dijkstra_map = DijkstraMap::new(80, 80, &targets, map, 64.0);
+        let dijkstra_map = DijkstraMap::new(map.width, map.height, &targets, map, 64.0);
         if let Some(destidx) = DijkstraMap::find_lowest_exit(&dijkstra_map, map.point2d_to_index(start), map) {
             return map.index_to_point2d(destidx)
         }
@@ -0,0 +1,162 @@
+//Plays a batch of seeded runs with one of the reference agents and writes one CSV row per run.
+//
+//  cargo run --release --bin simulate -- --runs 500 --agent greedy --seed 1 --out balance.csv
+//
+//Run seeds count up from --seed, so the same arguments always give the same CSV.
+use elfball::prelude::*;
+use std::fs;
+use std::process;
+use std::rc::Rc;
+
+struct SimOptions {
+    runs: u32,
+    agent: String,
+    seed: u64,
+    out: String,
+    max_steps: u32
+}
+
+struct RunResult {
+    seed: u64,
+    depth: u32,
+    turns: u32,
+    stats: RunStats,
+    cause_of_death: String,
+    final_party: Vec<String>
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let options = parse_options(&args);
+
+    let data = match GameData::load() {
+        Ok(data) => Rc::new(data),
+        Err(e) => {
+            eprintln!("Could not load the game data: {}", e);
+            process::exit(1)
+        }
+    };
+
+    let mut results = Vec::new();
+    for i in 0..options.runs {
+        let seed = options.seed + i as u64;
+        results.push(simulate_run(seed, &options, data.clone()));
+    }
+
+    if let Err(e) = fs::write(&options.out, to_csv(&options.agent, &results)) {
+        eprintln!("Could not write {}: {}", options.out, e);
+        process::exit(1)
+    }
+    print_summary(&options, &results);
+}
+
+fn simulate_run(seed: u64, options: &SimOptions, data: Rc<GameData>) -> RunResult {
+    let mut game = Game::new(seed, data);
+    let mut agent: Box<dyn Agent> = match options.agent.as_str() {
+        "random" => Box::new(RandomAgent::new(seed)),
+        _ => Box::new(GreedyAgent)
+    };
+
+    let steps = run_agent(&mut game, agent.as_mut(), options.max_steps);
+
+    let cause_of_death = match &game.stats.killed_by {
+        Some(killer) => format!("slain by {}", killer),
+        None => format!("alive after {} actions", steps)
+    };
+
+    return RunResult {
+        seed,
+        depth: game.world.max_depth,
+        turns: game.turn,
+        stats: game.stats.clone(),
+        cause_of_death,
+        final_party: game.stats.final_party.clone()
+    }
+}
+
+fn to_csv(agent: &str, results: &Vec<RunResult>) -> String {
+    let mut csv = String::from("seed,agent,depth,turns,forsaken_kills,beast_kills,rescued_elves,cause_of_death,final_party\n");
+    for r in results.iter() {
+        csv.push_str(&format!("{},{},{},{},{},{},{},{},{}\n",
+            r.seed, csv_field(agent), r.depth, r.turns,
+            r.stats.forsaken_kills, r.stats.beast_kills, r.stats.rescued_elves,
+            csv_field(&r.cause_of_death), csv_field(&r.final_party.join(";"))
+        ));
+    }
+    return csv
+}
+
+//Quotes a field if it has anything in it that would break the row
+fn csv_field(field: &str) -> String {
+    return if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn print_summary(options: &SimOptions, results: &Vec<RunResult>) {
+    if results.is_empty() { return }
+    let n = results.len() as f32;
+    let mean = |f: &dyn Fn(&RunResult) -> u32| results.iter().map(|r| f(r) as f32).sum::<f32>() / n;
+
+    println!("{} runs with the {} agent, written to {}", results.len(), options.agent, options.out);
+    println!("  depth:          mean {:.2}, deepest {}", mean(&|r| r.depth), results.iter().map(|r| r.depth).max().unwrap_or(0));
+    println!("  turns:          mean {:.1}", mean(&|r| r.turns));
+    println!("  forsaken kills: mean {:.2}", mean(&|r| r.stats.forsaken_kills));
+    println!("  beast kills:    mean {:.2}", mean(&|r| r.stats.beast_kills));
+    println!("  rescued elves:  mean {:.2}", mean(&|r| r.stats.rescued_elves));
+
+    let mut causes: Vec<(String, u32)> = Vec::new();
+    for r in results.iter() {
+        match causes.iter_mut().find(|c| c.0 == r.cause_of_death) {
+            Some(c) => c.1 += 1,
+            None => causes.push((r.cause_of_death.clone(), 1))
+        }
+    }
+    causes.sort_by(|a, b| b.1.cmp(&a.1));
+    println!("  outcomes:");
+    for (cause, count) in causes.iter() {
+        println!("    {:>5}  {}", count, cause);
+    }
+}
+
+fn parse_options(args: &Vec<String>) -> SimOptions {
+    let mut options = SimOptions {
+        runs: 100,
+        agent: String::from("greedy"),
+        seed: 1,
+        out: String::from("simulation.csv"),
+        max_steps: 5000
+    };
+
+    let mut i = 1;
+    while i < args.len() {
+        let value = args.get(i + 1);
+        match (args[i].as_str(), value) {
+            ("--runs", Some(v)) => options.runs = parse_number(&args[i], v),
+            ("--seed", Some(v)) => options.seed = parse_number(&args[i], v),
+            ("--max-steps", Some(v)) => options.max_steps = parse_number(&args[i], v),
+            ("--out", Some(v)) => options.out = v.clone(),
+            ("--agent", Some(v)) if v == "greedy" || v == "random" => options.agent = v.clone(),
+            ("--agent", _) => usage("--agent expects either greedy or random"),
+            (flag, _) => usage(&format!("unrecognised argument {}", flag))
+        }
+        i += 2;
+    }
+
+    return options
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> T {
+    return match value.parse::<T>() {
+        Ok(n) => n,
+        Err(_) => usage(&format!("{} expects a whole number", flag))
+    }
+}
+
+fn usage(problem: &str) -> ! {
+    eprintln!("{}", problem);
+    eprintln!("usage: simulate [--runs N] [--agent greedy|random] [--seed S] [--out FILE] [--max-steps N]");
+    process::exit(1)
+}
@@ -0,0 +1,128 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+/// The look and feel of a stretch of depths, as written in the biomes file: how its floors are grown, what colour
+/// they're drawn in, and what the party is told when they step into one.
+#[derive(Clone, Deserialize)]
+pub struct BiomeDef {
+    pub name: String,
+    //The first and last depth it covers. Leaving out the last one covers every depth from there down.
+    pub from: u32,
+    #[serde(default)]
+    pub to: Option<u32>,
+    //Shown in the log when the party steps through a portal onto one of its floors
+    pub arrival: String,
+    #[serde(default)]
+    pub palette: Palette,
+    #[serde(default)]
+    pub terrain: TerrainMix
+}
+impl BiomeDef {
+    pub fn covers(&self, depth: u32) -> bool { depth >= self.from && self.to.map_or(true, |to| depth <= to) }
+}
+
+/// What colour each kind of ground is drawn in. The portals keep their own colours everywhere, so they always stand out.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Palette {
+    pub floor: (u8, u8, u8),
+    pub tree: (u8, u8, u8),
+    pub undergrowth: (u8, u8, u8),
+    pub shallow_stream: (u8, u8, u8),
+    pub deep_water: (u8, u8, u8),
+    pub fallen_log: (u8, u8, u8),
+    pub stone: (u8, u8, u8),
+    pub clearing: (u8, u8, u8)
+}
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette {
+            floor: BROWN1,
+            tree: GREEN,
+            undergrowth: DARK_GREEN,
+            shallow_stream: (90, 150, 210),
+            deep_water: (30, 60, 170),
+            fallen_log: (140, 90, 45),
+            stone: GREY50,
+            clearing: (130, 180, 70)
+        }
+    }
+}
+
+/// How a biome's floors get grown. Anything left out keeps the value the forest has always used.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct TerrainMix {
+    //Chance out of 100 that a tile starts out as a tree, before the cellular automata smooth everything over
+    pub tree_density: i32,
+    //How many passes the cellular automata make
+    pub iterations: u32,
+    //Chance out of 100 that open ground along the treeline is grown over with undergrowth
+    pub undergrowth: i32,
+    //Chance out of 100 that a stream runs through the floor
+    pub stream: i32,
+    //Chance out of 100 that each tile down the middle of a stream is too deep to wade
+    pub deep_water: i32,
+    //Chance out of 100 that a trail or plaza tile has a fallen log lying across it
+    pub fallen_logs: i32
+}
+impl Default for TerrainMix {
+    fn default() -> TerrainMix {
+        TerrainMix {
+            tree_density: 25,
+            iterations: 23,
+            undergrowth: 50,
+            stream: 50,
+            deep_water: 67,
+            fallen_logs: 3
+        }
+    }
+}
+
+//The biomes have to cover every depth in order, one after another, with the last reaching all the way down
+pub fn validate_biomes(biomes: &Vec<BiomeDef>) -> Result<(), String> {
+    if biomes.is_empty() {
+        return Err(String::from("at least one biome has to be defined"))
+    }
+
+    let mut seen = HashSet::new();
+    let mut next_depth = 1;
+    for (i, biome) in biomes.iter().enumerate() {
+        if biome.name.trim().is_empty() {
+            return Err(String::from("a biome is missing its name"))
+        }
+        if !seen.insert(biome.name.as_str()) {
+            return Err(format!("the biome \"{}\" is defined more than once", biome.name))
+        }
+        if biome.from != next_depth {
+            return Err(format!("the {} starts at depth {}, but the biome before it leaves off at depth {}", biome.name, biome.from, next_depth))
+        }
+        match biome.to {
+            Some(to) if to < biome.from =>
+                return Err(format!("the {} ends at depth {}, before it starts", biome.name, to)),
+            Some(to) => next_depth = to + 1,
+            None if i != biomes.len() - 1 =>
+                return Err(format!("the {} has no last depth, so it has to be the final biome", biome.name)),
+            None => {}
+        }
+        if biome.arrival.trim().is_empty() {
+            return Err(format!("the {} needs an arrival message", biome.name))
+        }
+
+        let terrain = &biome.terrain;
+        for (name, chance) in [("tree_density", terrain.tree_density), ("undergrowth", terrain.undergrowth), ("stream", terrain.stream),
+                               ("deep_water", terrain.deep_water), ("fallen_logs", terrain.fallen_logs)].iter() {
+            if *chance < 0 || *chance > 100 {
+                return Err(format!("the {} has a {} chance of {}, it has to be between 0 and 100", biome.name, name, chance))
+            }
+        }
+        if terrain.iterations > 100 {
+            return Err(format!("the {} runs the cellular automata {} times, it can't be more than 100", biome.name, terrain.iterations))
+        }
+    }
+    if biomes[biomes.len() - 1].to.is_some() {
+        return Err(String::from("the final biome needs to leave out its last depth, so every depth further down is covered"))
+    }
+
+    return Ok(())
+}
@@ -2,6 +2,7 @@ use crate::prelude::*;
 
 pub const UI_CUTOFF: Point = Point { x: 36, y: 22 };
 
+#[derive(Serialize, Deserialize)]
 pub struct Camera {
     pub min_x: i32,
     pub max_x: i32,
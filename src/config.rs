@@ -0,0 +1,67 @@
+use crate::prelude::*;
+
+/// Settings testers can change without touching the code, read from `res/config.ron`.
+/// Anything left out of the file keeps the value the game has always used.
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    //Size of every generated floor, in tiles
+    pub map_width: i32,
+    pub map_height: i32,
+    //Size of the game window, in pixels
+    pub window_width: i32,
+    pub window_height: i32,
+    //How many elves the player's party can hold
+    pub party_cap: usize,
+    //The classes every run starts out with
+    pub starting_party: Vec<String>,
+    //How far each kind of party can see
+    pub player_view_range: i32,
+    pub band_view_range: i32,
+    pub beast_view_range: i32
+}
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            map_width: 64,
+            map_height: 64,
+            window_width: 800,
+            window_height: 640,
+            party_cap: 10,
+            starting_party: vec![String::from("Guardian"), String::from("Bard")],
+            player_view_range: 6,
+            band_view_range: 6,
+            beast_view_range: 9
+        }
+    }
+}
+
+//Keeps every setting within what the game can cope with
+pub fn validate_config(config: &Config) -> Result<(), String> {
+    for (name, size) in [("map_width", config.map_width), ("map_height", config.map_height)].iter() {
+        if *size < 20 || *size > 256 {
+            return Err(format!("{} is {}, it has to be between 20 and 256", name, size))
+        }
+    }
+    for (name, size) in [("window_width", config.window_width), ("window_height", config.window_height)].iter() {
+        if *size < 160 || size % 16 != 0 {
+            return Err(format!("{} is {}, it has to be a multiple of 16 and at least 160", name, size))
+        }
+    }
+    if config.party_cap == 0 || config.party_cap > 20 {
+        return Err(format!("party_cap is {}, it has to be between 1 and 20", config.party_cap))
+    }
+    if config.starting_party.is_empty() {
+        return Err(String::from("the starting party needs at least one class"))
+    }
+    if config.starting_party.len() > config.party_cap {
+        return Err(format!("the starting party has {} classes, more than the party cap of {}", config.starting_party.len(), config.party_cap))
+    }
+    for (name, range) in [("player_view_range", config.player_view_range), ("band_view_range", config.band_view_range), ("beast_view_range", config.beast_view_range)].iter() {
+        if *range < 1 || *range > 30 {
+            return Err(format!("{} is {}, it has to be between 1 and 30", name, range))
+        }
+    }
+
+    return Ok(())
+}
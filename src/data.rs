@@ -0,0 +1,217 @@
+use crate::prelude::*;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DATA_DIR: &str = "res";
+pub const CLASSES_FILE: &str = "classes.ron";
+pub const ABILITIES_FILE: &str = "abilities.ron";
+pub const SPAWNS_FILE: &str = "spawns.ron";
+pub const CONFIG_FILE: &str = "config.ron";
+pub const PREFABS_FILE: &str = "prefabs.ron";
+pub const BIOMES_FILE: &str = "biomes.ron";
+
+/// Everything the game reads from data files instead of having it baked in. Loaded once at startup.
+#[derive(Default)]
+pub struct GameData {
+    pub classes: Vec<ClassDef>,
+    pub abilities: Vec<AbilityDef>,
+    pub spawn_tables: Vec<SpawnTable>,
+    pub config: Config,
+    pub prefabs: Vec<PrefabDef>,
+    pub biomes: Vec<BiomeDef>
+}
+
+pub enum DataError {
+    Io { path: PathBuf, error: std::io::Error },
+    Parse { path: PathBuf, message: String },
+    Invalid { path: PathBuf, problem: String }
+}
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataError::Io { path, error } => write!(f, "could not read {} ({})", path.display(), error),
+            DataError::Parse { path, message } => write!(f, "{} is not valid: {}", path.display(), message),
+            DataError::Invalid { path, problem } => write!(f, "{}: {}", path.display(), problem),
+        }
+    }
+}
+
+impl GameData {
+    /// Loads and validates every data file in `res/`.
+    pub fn load() -> Result<GameData, DataError> { GameData::load_from(DATA_DIR) }
+
+    pub fn load_from<P: AsRef<Path>>(dir: P) -> Result<GameData, DataError> {
+        let abilities_path = dir.as_ref().join(ABILITIES_FILE);
+        let abilities: Vec<AbilityDef> = read_ron(&abilities_path)?;
+        validate_abilities(&abilities)
+            .and_then(|_| check_caster_ability(&abilities))
+            .map_err(|problem| DataError::Invalid { path: abilities_path, problem })?;
+
+        let classes_path = dir.as_ref().join(CLASSES_FILE);
+        let classes: Vec<ClassDef> = read_ron(&classes_path)?;
+        validate_classes(&classes)
+            .and_then(|_| check_class_abilities(&classes, &abilities))
+            .map_err(|problem| DataError::Invalid { path: classes_path, problem })?;
+
+        let biomes_path = dir.as_ref().join(BIOMES_FILE);
+        let biomes: Vec<BiomeDef> = read_ron(&biomes_path)?;
+        validate_biomes(&biomes).map_err(|problem| DataError::Invalid { path: biomes_path, problem })?;
+
+        let spawns_path = dir.as_ref().join(SPAWNS_FILE);
+        let spawn_tables: Vec<SpawnTable> = read_ron(&spawns_path)?;
+        validate_spawn_tables(&spawn_tables)
+            .and_then(|_| check_spawn_table_biomes(&spawn_tables, &biomes))
+            .map_err(|problem| DataError::Invalid { path: spawns_path, problem })?;
+
+        //Unlike the rest, the config file is optional; without one everything keeps its default
+        let config_path = dir.as_ref().join(CONFIG_FILE);
+        let config: Config = if config_path.exists() { read_ron(&config_path)? } else { Config::default() };
+        validate_config(&config)
+            .and_then(|_| check_starting_party(&config, &classes))
+            .map_err(|problem| DataError::Invalid { path: config_path, problem })?;
+
+        let prefabs_path = dir.as_ref().join(PREFABS_FILE);
+        let prefabs: Vec<PrefabDef> = read_ron(&prefabs_path)?;
+        validate_prefabs(&prefabs)
+            .and_then(|_| check_prefab_sizes(&prefabs, &config))
+            .map_err(|problem| DataError::Invalid { path: prefabs_path, problem })?;
+
+        return Ok(GameData { classes, abilities, spawn_tables, config, prefabs, biomes })
+    }
+
+    pub fn class(&self, name: &str) -> Option<&ClassDef> { self.classes.iter().find(|c| c.name == name) }
+    pub fn ability(&self, name: &str) -> Option<&AbilityDef> { self.abilities.iter().find(|a| a.name == name) }
+    //The colour someone is named in: elves go by their class, everyone else by what they are
+    pub fn color_of(&self, who: &Combatant) -> (u8, u8, u8) {
+        return match who.kind {
+            ActorKind::Elves => self.class(&who.class).map_or(PLAYER_COLOR, |c| c.color),
+            ActorKind::Forsaken if who.class == CASTER_CLASS => CASTER_COLOR,
+            ActorKind::Forsaken => WARRIOR_COLOR,
+            ActorKind::Beast => BEAST_COLOR,
+            ActorKind::NonActor => WHITE
+        }
+    }
+    //A table for the depth's biome wins over the general one. Validation makes sure the general tables cover
+    //every depth, the last one reaching all the way down.
+    pub fn spawn_table(&self, depth: u32) -> &SpawnTable {
+        let biome = &self.biome(depth).name;
+        return self.spawn_tables.iter().find(|t| t.biome.as_ref() == Some(biome) && t.covers(depth))
+            .or_else(|| self.spawn_tables.iter().find(|t| t.biome.is_none() && t.covers(depth)))
+            .unwrap_or(&self.spawn_tables[self.spawn_tables.len() - 1])
+    }
+    //Same as the spawn tables, validation makes sure every depth has a biome
+    pub fn biome(&self, depth: u32) -> &BiomeDef {
+        return self.biomes.iter().find(|b| b.covers(depth)).unwrap_or(&self.biomes[self.biomes.len() - 1])
+    }
+}
+
+fn read_ron<T: serde::de::DeserializeOwned>(path: &PathBuf) -> Result<T, DataError> {
+    let contents = fs::read_to_string(path).map_err(|error| DataError::Io { path: path.clone(), error })?;
+    return ron::de::from_str(&contents).map_err(|e| DataError::Parse { path: path.clone(), message: e.to_string() })
+}
+
+//What each file can get wrong on its own is checked next to the type it's read into. The checks from here on are
+//for names and sizes one file takes from another.
+
+fn check_caster_ability(abilities: &Vec<AbilityDef>) -> Result<(), String> {
+    if !abilities.iter().any(|a| a.name == CASTER_ABILITY) {
+        return Err(format!("the Forsaken casters need a \"{}\" ability, but none is defined", CASTER_ABILITY))
+    }
+
+    return Ok(())
+}
+
+fn check_class_abilities(classes: &Vec<ClassDef>, abilities: &Vec<AbilityDef>) -> Result<(), String> {
+    for class in classes.iter() {
+        for ability in class.abilities.iter() {
+            if !abilities.iter().any(|a| &a.name == ability) {
+                return Err(format!("the class \"{}\" has the ability \"{}\", which isn't in {}", class.name, ability, ABILITIES_FILE))
+            }
+        }
+    }
+
+    return Ok(())
+}
+
+fn check_spawn_table_biomes(tables: &Vec<SpawnTable>, biomes: &Vec<BiomeDef>) -> Result<(), String> {
+    for (i, table) in tables.iter().enumerate() {
+        if let Some(name) = &table.biome {
+            let biome = biomes.iter().find(|b| &b.name == name)
+                .ok_or(format!("spawn table {} is for the biome \"{}\", which isn't in {}", i + 1, name, BIOMES_FILE))?;
+            let overlaps = table.from <= biome.to.unwrap_or(u32::MAX) && biome.from <= table.to.unwrap_or(u32::MAX);
+            if !overlaps {
+                return Err(format!("spawn table {} is for the {}, but none of its depths are in that biome", i + 1, name))
+            }
+        }
+    }
+
+    return Ok(())
+}
+
+fn check_starting_party(config: &Config, classes: &Vec<ClassDef>) -> Result<(), String> {
+    for name in config.starting_party.iter() {
+        if !classes.iter().any(|c| &c.name == name) {
+            return Err(format!("the starting party has the class \"{}\", which isn't in {}", name, CLASSES_FILE))
+        }
+    }
+
+    return Ok(())
+}
+
+fn check_prefab_sizes(prefabs: &Vec<PrefabDef>, config: &Config) -> Result<(), String> {
+    for prefab in prefabs.iter() {
+        if prefab.width() > config.map_width - 2 || prefab.height() > config.map_height - 2 {
+            return Err(format!("the prefab \"{}\" is {}x{}, too big to fit in a {}x{} map", prefab.name, prefab.width(), prefab.height(), config.map_width, config.map_height))
+        }
+    }
+
+    return Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(from: u32, to: Option<u32>, biome: Option<&str>, bands: u32) -> SpawnTable {
+        SpawnTable {
+            from, to, biome: biome.map(String::from),
+            bands, band_size: (1, 2), members: vec![(ForsakenKind::Warrior, 1)],
+            beasts: 0, elves: 3,
+            band_speed: PLAYER_SPEED, beast_speed: PLAYER_SPEED
+        }
+    }
+    fn biome(name: &str, from: u32, to: Option<u32>) -> BiomeDef {
+        BiomeDef { name: String::from(name), from, to, arrival: String::from("..."), palette: Palette::default(), terrain: TerrainMix::default() }
+    }
+    fn biomes() -> Vec<BiomeDef> { vec![biome("Greenwood", 1, Some(2)), biome("Blighted Wood", 3, None)] }
+
+    #[test]
+    fn biome_tables_have_to_name_a_biome_they_can_apply_to() {
+        let known = vec![table(1, None, None, 10), table(3, Some(4), Some("Blighted Wood"), 5)];
+        assert!(check_spawn_table_biomes(&known, &biomes()).is_ok());
+        let unknown = vec![table(1, None, None, 10), table(3, None, Some("Frozen Pines"), 5)];
+        assert!(check_spawn_table_biomes(&unknown, &biomes()).is_err());
+        let outside = vec![table(1, None, None, 10), table(1, Some(2), Some("Blighted Wood"), 5)];
+        assert!(check_spawn_table_biomes(&outside, &biomes()).is_err());
+    }
+
+    #[test]
+    fn biome_table_wins_over_the_general_one() {
+        let data = GameData {
+            spawn_tables: vec![table(1, Some(2), None, 10), table(3, None, None, 11), table(3, Some(4), Some("Blighted Wood"), 5)],
+            biomes: biomes(),
+            ..Default::default()
+        };
+        assert_eq!(data.spawn_table(1).bands, 10);
+        assert_eq!(data.spawn_table(3).bands, 5);
+        assert_eq!(data.spawn_table(4).bands, 5);
+        //Past the biome table's depths, the general table takes over again
+        assert_eq!(data.spawn_table(5).bands, 11);
+    }
+
+    #[test]
+    fn shipped_data_files_load() {
+        if let Err(e) = GameData::load() { panic!("{}", e) }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::prelude::*;
+
+//Everything that happened during a turn, in order. Gameplay code only describes what happened here;
+//the log, the run statistics and anything else interested read the queue and decide what to do with it.
+pub type EventQueue = Vec<GameEvent>;
+
+#[derive(Clone)]
+pub enum GameEvent {
+    DamageDealt { attacker: Combatant, target: Combatant, amount: i32 },
+    MemberSlain { victim: Combatant, side: ActorTag, killer: Option<Combatant> },
+    PartyDefeated { party: Combatant },
+    PlayerPartyWiped { killer: Option<Combatant> },
+    ElfRecruited { elf: Combatant },
+    PartyFull,
+    AbilityUsed { caster: Combatant, ability: String, target: Option<Combatant>, amount: Option<i32>, healing: bool },
+    AbilityFailed { reason: AbilityFailure },
+    AbilityOnCooldown { caster: Combatant, ability: String },
+    PortalEntered { depth: u32 },
+    ReturnPortalEntered { depth: u32 }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AbilityFailure {
+    NeedsTarget, InvalidTarget, OutOfRange, NoTrees
+}
+
+//Who an event is about: either a single party member, or a whole party/object. Party-wide combatants have no class.
+#[derive(Clone)]
+pub struct Combatant {
+    pub name: String,
+    pub class: String,
+    pub kind: ActorKind
+}
+impl Combatant {
+    pub fn from_member(member: &PartyMember, kind: ActorKind) -> Combatant {
+        Combatant { name: member.name.clone(), class: member.class.clone(), kind }
+    }
+    pub fn from_object(obj: &Object) -> Combatant {
+        Combatant { name: obj.name.clone(), class: String::new(), kind: obj.kind }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::prelude::*;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TurnState {
+    Player, AI, GameOver
+}
+
+/// The simulation core of a run. Holds everything the turn pipeline touches, and needs no window to advance.
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub passed_turn: bool,
+    //The floor the player is heading to, once they've stepped on a portal
+    pub travel_to: Option<u32>,
+    pub player_death: bool,
+    pub turn_state: TurnState,
+    pub turn: u32,
+    pub world: World,
+    pub logs: LogBuffer,
+    pub player_targets: TargetList,
+    pub stored_abilities: Vec<StoredAbility>,
+    pub stats: RunStats,
+    pub replay: Replay,
+    //Only lives for the length of a step, so there's nothing worth saving
+    #[serde(skip)]
+    pub events: EventQueue
+}
+impl Game {
+    /// Starts a new run from a seed. The same seed and data always give the same run.
+    pub fn new(seed: u64, data: Rc<GameData>) -> Game {
+        let mut logs = LogBuffer::new();
+        logs.update_logs(LogMessage::new().category(LogCategory::Help)
+            .add_part("Press", ColorPair::new(WHITE, GREY10))
+            .add_part("Slash (/)", ColorPair::new(GOLD, GREY10))
+            .add_part("to view the controls at any time.", ColorPair::new(WHITE, GREY10))
+        );
+        logs.update_logs(LogMessage::new()
+            .add_part("Your ancestors have called upon you to save these lands from the bestial scourge they are beset against.", ColorPair::new(WHITE, GREY10))
+            .add_part("Gather more", ColorPair::new(WHITE, GREY10))
+            .add_part("Elves (☻)", ColorPair::new(LIME_GREEN, GREY10))
+            .add_part("to expand your party of woodland defenders.", ColorPair::new(WHITE, GREY10))
+            .add_part("Beware of", ColorPair::new(WHITE, GREY10))
+            .add_part("Beasts (b)", ColorPair::new(RED, GREY10))
+            .add_part("that stalk these woods, and", ColorPair::new(WHITE, GREY10))
+            .add_part("the Forsaken (☺),", ColorPair::new(PURPLE, GREY10))
+            .add_part("your fallen bretheren. Look for", ColorPair::new(WHITE, GREY10))
+            .add_part("Portals (§)", ColorPair::new(CYAN, GREY10))
+            .add_part("to travel to new parts of the forest. Good luck...", ColorPair::new(WHITE, GREY10))
+        );
+
+        let mut game = Game {
+            passed_turn: false,
+            travel_to: None,
+            player_death: false,
+            turn_state: TurnState::Player,
+            turn: 0,
+            world: World::new_game(seed, data),
+            logs,
+            player_targets: TargetList::new(),
+            stored_abilities: Vec::new(),
+            stats: RunStats::new(),
+            replay: Replay::new(seed),
+            events: EventQueue::new()
+        };
+
+        for member in game.world.objects[0].members.iter() {
+            game.stats.add_to_roster(member, 0, game.world.depth);
+        }
+
+        //Run the systems once so vision, targets and abilities are ready before the first action
+        game.exec_all_systems();
+        game.dispatch_events();
+        return game
+    }
+
+    /// Feeds one action through the whole turn pipeline, and records it so the run can be replayed.
+    pub fn step(&mut self, action: Actions) {
+        if self.is_over() { return }
+        self.events.clear();
+        //Everything written while this action plays out belongs to the turn it was taken on
+        self.logs.set_turn(self.turn);
+
+        process_action(self, action);
+        self.exec_all_systems();
+
+        if let Some(depth) = self.travel_to.take() {
+            self.world.change_floor(depth);
+            self.player_targets.reset_targets(&self.world.objects, &self.world.map);
+            self.exec_all_systems();
+        }
+        self.dispatch_events();
+
+        let hash = self.state_hash();
+        self.replay.record(action, hash);
+    }
+
+    pub fn is_over(&self) -> bool { self.turn_state == TurnState::GameOver }
+
+    pub fn refresh_stored_abilities(&mut self) {
+        self.stored_abilities.clear();
+        let player_id = self.world.player_id();
+        for (i, member) in self.world.objects[0].members.iter_mut().enumerate() {
+            for (j, ability) in member.abilities.iter().enumerate() {
+                self.stored_abilities.push(StoredAbility::new(ability.ability.clone(), player_id, i, j, ability.on_cooldown));
+            }
+        }
+    }
+
+    //Hands everything that happened this step to the parts of the game that listen for it
+    fn dispatch_events(&mut self) {
+        self.logs.log_events(&self.events, &self.world.data);
+        self.stats.tally_events(&self.events, self.turn, self.world.depth);
+        let party = &self.world.objects[0].members;
+        if !party.is_empty() {
+            self.stats.final_party = party.iter().map(|m| m.class.clone()).collect();
+        }
+    }
+
+    //Lets everything else act in initiative order, until the player's initiative comes due again
+    fn run_schedule(&mut self) {
+        let player_id = self.world.player_id();
+        //Nothing would ever come due for the player, so don't wait on it
+        if self.world.objects[0].initiative.as_ref().map_or(true, |init| init.speed == 0) { return }
+        loop {
+            let next = match due_actors(&self.world.objects, self.world.depth).first() {
+                Some(due) => due.0,
+                None => {
+                    tick_initiative(&mut self.world.objects, self.world.depth);
+                    continue
+                }
+            };
+            if next == player_id { return }
+
+            process_enemy_turn(next, &mut self.world.objects, &mut self.world.map, &self.world.data, self.world.depth, &mut self.world.rng.ai, &mut self.events);
+            spend_initiative(&mut self.world.objects, next);
+            process_fov(&mut self.world.objects, &mut self.world.map, self.world.depth);
+        }
+    }
+
+    fn exec_all_systems(&mut self) {
+        apply_party_modifiers(&mut self.world.objects[0].members);
+
+        //Execute the systems and shit
+        process_fov(&mut self.world.objects, &mut self.world.map, self.world.depth);
+        process_combat(&mut self.world.objects, &mut self.world.entities, &mut self.events, &mut self.player_death, &mut self.player_targets, &self.world.map);
+        update_blocked_tiles(&mut self.world.objects, &mut self.world.map, self.world.depth);
+        check_player_collisions(self);
+
+        if self.passed_turn {
+            self.turn += 1;
+            process_all_cooldowns(&mut self.world.objects);
+            reset_attack_capabilities(&mut self.world.objects[0].members);
+            let player_id = self.world.player_id();
+            spend_initiative(&mut self.world.objects, player_id);
+            self.turn_state = TurnState::AI;
+            self.passed_turn = false;
+            process_fov(&mut self.world.objects, &mut self.world.map, self.world.depth);
+        }
+
+        if self.turn_state == TurnState::AI {
+            self.run_schedule();
+            process_fov(&mut self.world.objects, &mut self.world.map, self.world.depth);
+            process_combat(&mut self.world.objects, &mut self.world.entities, &mut self.events, &mut self.player_death, &mut self.player_targets, &self.world.map);
+            update_blocked_tiles(&mut self.world.objects, &mut self.world.map, self.world.depth);
+            self.turn_state = TurnState::Player;
+        }
+
+        update_player_memory(&mut self.world.objects);
+        update_targets_in_vision(self);
+
+        clean_party_modifiers(&mut self.world.objects[0].members);
+        self.refresh_stored_abilities();
+
+        if self.player_death {
+            self.turn_state = TurnState::GameOver;
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! The simulation core of Elfball: the world, its map and objects, the party and the systems that run a turn.
+//! Nothing in here needs a window, so the game can be stepped from tests, tools and other binaries.
+//! The `elfball` binary is a windowed frontend over this crate.
+
+pub mod game;
+pub mod turn;
+pub mod world;
+pub mod map;
+pub mod map_builders;
+pub mod biomes;
+pub mod camera;
+pub mod object;
+pub mod spawns;
+pub mod party;
+pub mod actions;
+pub mod systems;
+pub mod logs;
+pub mod log_export;
+pub mod ai;
+pub mod rng;
+pub mod save;
+pub mod replay;
+pub mod events;
+pub mod stats;
+pub mod data;
+pub mod config;
+pub mod morgue;
+pub mod scores;
+pub mod agents;
+
+/// Everything a frontend or tool needs, in one import.
+pub mod prelude {
+    pub use bracket_lib::prelude::*;
+    pub use crate::game::*;
+    pub use crate::world::*;
+    pub use crate::map::*;
+    pub use crate::map_builders::*;
+    pub use crate::biomes::*;
+    pub use crate::camera::*;
+    pub use crate::object::*;
+    pub use crate::spawns::*;
+    pub use crate::party::*;
+    pub use crate::actions::*;
+    pub use crate::systems::*;
+    pub use crate::logs::*;
+    pub use crate::log_export::*;
+    pub use crate::ai::*;
+    pub use crate::rng::*;
+    pub use crate::save::*;
+    pub use crate::replay::*;
+    pub use crate::turn::*;
+    pub use crate::events::*;
+    pub use crate::stats::*;
+    pub use crate::data::*;
+    pub use crate::config::*;
+    pub use crate::morgue::*;
+    pub use crate::scores::*;
+    pub use crate::agents::*;
+    pub use serde::{Serialize, Deserialize};
+
+    use std::cmp::Reverse;
+
+    pub const CONSOLE_W: i32 = 80;
+    pub const CONSOLE_H: i32 = 60;
+
+    pub const MAP_CON: usize = 0;
+    pub const TEXT_CON: usize = 1;
+
+    //Constant point values used as directional indicators
+    pub const DL_LEFT: Point = Point { x: -1, y: 0 };
+    pub const DL_RIGHT: Point = Point { x: 1, y: 0 };
+    pub const DL_UP: Point = Point { x: 0, y: -1 };
+    pub const DL_DOWN: Point = Point { x: 0, y: 1 };
+
+    //Type alias for a vec of entity IDs and initiative values
+    pub type InitList = Vec<(EntityId, u32)>;
+
+    pub trait InitListTrait {
+        fn add_object(&mut self, id: EntityId, init: u32);
+        fn sort(&mut self);
+    }
+    impl InitListTrait for InitList {
+        //Adds a new object to the list
+        fn add_object(&mut self, id: EntityId, init: u32) {
+            self.push((id, init));
+        }
+        //Sorts by descending initiative order
+        fn sort(&mut self) {
+            self.sort_by_key(|a| Reverse(a.1));
+        }
+    }
+
+    pub trait Neighbor {
+        fn get_neighbors(&self) -> Vec<Point>;
+    }
+    impl Neighbor for Point {
+        fn get_neighbors(&self) -> Vec<Point> {
+            return vec![
+                *self + DL_UP,
+                *self + DL_DOWN,
+                *self + DL_LEFT,
+                *self + DL_RIGHT,
+                *self + DL_UP + DL_LEFT,
+                *self + DL_UP + DL_RIGHT,
+                *self + DL_DOWN + DL_LEFT,
+                *self + DL_DOWN + DL_RIGHT
+            ]
+        }
+    }
+}
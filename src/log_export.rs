@@ -0,0 +1,131 @@
+use crate::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const LOG_EXPORT_DIR: &str = "logs";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    PlainText, Ansi, Html
+}
+impl LogFormat {
+    pub const ALL: [LogFormat; 3] = [LogFormat::PlainText, LogFormat::Ansi, LogFormat::Html];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LogFormat::PlainText => "txt",
+            LogFormat::Ansi => "ansi",
+            LogFormat::Html => "html",
+        }
+    }
+}
+
+impl LogBuffer {
+    /// Writes out the history, one message per line with its turn, leaving out any `hidden` categories.
+    /// Plain text drops the colors, ANSI keeps them as 24-bit terminal escapes and HTML as a standalone page.
+    pub fn export(&self, format: LogFormat, hidden: &[LogCategory]) -> String {
+        let entries = self.entries().iter().filter(|e| !hidden.contains(&e.message.category));
+        let mut out = String::new();
+
+        if format == LogFormat::Html {
+            out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Elfball message log</title>\n");
+            out.push_str(&format!("<style>body {{ background: {}; font-family: monospace; white-space: pre-wrap; }}</style>\n", html_color(GREY10.into())));
+            out.push_str("</head>\n<body>\n");
+        }
+
+        for entry in entries {
+            let turn = format!("[{:>4}] ", entry.turn);
+            match format {
+                LogFormat::PlainText => {
+                    out.push_str(&turn);
+                    out.push_str(&entry.message.parts.join(" "));
+                }
+                LogFormat::Ansi => {
+                    out.push_str(&turn);
+                    for (i, part) in entry.message.parts.iter().enumerate() {
+                        let colors = entry.message.colors[i];
+                        if i > 0 { out.push(' '); }
+                        out.push_str(&format!("{}{}{}\x1b[0m", ansi_color(38, colors.fg), ansi_color(48, colors.bg), part));
+                    }
+                }
+                LogFormat::Html => {
+                    out.push_str(&format!("<div><span style=\"color: {}\">{}</span>", html_color(GREY50.into()), turn));
+                    for (i, part) in entry.message.parts.iter().enumerate() {
+                        let colors = entry.message.colors[i];
+                        if i > 0 { out.push(' '); }
+                        out.push_str(&format!("<span style=\"color: {}; background: {}\">{}</span>",
+                            html_color(colors.fg), html_color(colors.bg), escape_html(part)));
+                    }
+                    out.push_str("</div>");
+                }
+            }
+            out.push('\n');
+        }
+
+        if format == LogFormat::Html {
+            out.push_str("</body>\n</html>\n");
+        }
+        return out
+    }
+
+    /// Exports the history into `dir` in every format, named after the seed and turn, and returns the files written.
+    pub fn export_to_disk<P: AsRef<Path>>(&self, dir: P, seed: u64, turn: u32, hidden: &[LogCategory]) -> std::io::Result<Vec<PathBuf>> {
+        fs::create_dir_all(&dir)?;
+        let mut written = Vec::new();
+        for format in LogFormat::ALL.iter() {
+            let path = dir.as_ref().join(format!("log-{}-turn{}.{}", seed, turn, format.extension()));
+            fs::write(&path, self.export(*format, hidden))?;
+            written.push(path);
+        }
+        return Ok(written)
+    }
+}
+
+fn to_u8(channel: f32) -> u8 { (channel.max(0.0).min(1.0) * 255.0).round() as u8 }
+
+//A 24-bit color escape, `layer` being 38 for the text and 48 for the background
+fn ansi_color(layer: u8, color: RGBA) -> String {
+    return format!("\x1b[{};2;{};{};{}m", layer, to_u8(color.r), to_u8(color.g), to_u8(color.b))
+}
+
+fn html_color(color: RGBA) -> String {
+    return format!("#{:02x}{:02x}{:02x}", to_u8(color.r), to_u8(color.g), to_u8(color.b))
+}
+
+fn escape_html(text: &str) -> String {
+    return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_special_characters_are_escaped() {
+        assert_eq!(escape_html("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+        assert_eq!(escape_html("\"quoted\""), "&quot;quoted&quot;");
+        //Ampersands go first, so what the other escapes add doesn't get escaped again
+        assert_eq!(escape_html("&lt;"), "&amp;lt;");
+    }
+
+    #[test]
+    fn html_export_escapes_messages() {
+        let mut logs = LogBuffer::new();
+        logs.update_logs(LogMessage::new().add_part("<script>alert(1)</script> & co", ColorPair::new(WHITE, GREY10)));
+        let html = logs.export(LogFormat::Html, &[]);
+
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt; &amp; co"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn plain_text_export_leaves_hidden_categories_out() {
+        let mut logs = LogBuffer::new();
+        logs.update_logs(LogMessage::new().add_part("a fight", ColorPair::new(WHITE, GREY10)).category(LogCategory::Combat));
+        logs.update_logs(LogMessage::new().add_part("a portal", ColorPair::new(WHITE, GREY10)).category(LogCategory::System));
+        let text = logs.export(LogFormat::PlainText, &[LogCategory::Combat]);
+
+        assert!(!text.contains("a fight"));
+        assert!(text.contains("a portal"));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use std::cell::{Ref, RefCell};
 
 pub const LOG_BOX: Rect = Rect {
     x1: 1,
@@ -7,18 +8,90 @@ pub const LOG_BOX: Rect = Rect {
     y2: CONSOLE_H - 1
 };
 
-pub type LogBuffer = Vec<LogMessage>;
+//What a message is about, so the log viewer can show just the kinds you care about
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LogCategory {
+    Combat, Ability, Party, System, Help
+}
+impl LogCategory {
+    pub const ALL: [LogCategory; 5] = [LogCategory::Combat, LogCategory::Ability, LogCategory::Party, LogCategory::System, LogCategory::Help];
 
-pub trait LogBufferTrait {
-    fn format(&self) -> TextBuilder;
-    fn update_logs(&mut self, message: LogMessage);
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogCategory::Combat => "Combat",
+            LogCategory::Ability => "Ability",
+            LogCategory::Party => "Party",
+            LogCategory::System => "System",
+            LogCategory::Help => "Help",
+        }
+    }
+}
+impl Default for LogCategory {
+    fn default() -> LogCategory { LogCategory::System }
 }
 
-impl LogBufferTrait for LogBuffer {
-    fn format(&self) -> TextBuilder {
-        let mut builder = TextBuilder::empty();
+//How many wrapped lines the sidebar log box shows
+pub const SIDEBAR_LOG_LINES: usize = 24;
+
+/// Every message of the run, oldest first, each tagged with the turn it was written on. Nothing is ever dropped,
+/// the sidebar just shows the most recent few and the log viewer can scroll back through the rest.
+#[derive(Default, Serialize, Deserialize)]
+pub struct LogBuffer {
+    entries: Vec<LogEntry>,
+    //The turn new messages get tagged with
+    turn: u32,
+    #[serde(skip)]
+    history: RefCell<HistoryCache>
+}
+
+//The history as it was last wrapped for the log viewer. As long as the width and hidden categories stay the same,
+//only messages added since then need wrapping.
+#[derive(Default)]
+struct HistoryCache {
+    max_width: usize,
+    hidden: Vec<LogCategory>,
+    //How many entries have been wrapped into `lines` so far
+    wrapped: usize,
+    lines: Vec<LogMessage>
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LogEntry {
+    pub turn: u32,
+    pub message: LogMessage
+}
+
+impl LogBuffer {
+    pub fn new() -> LogBuffer { LogBuffer::default() }
 
-        for (n, message) in self.iter().rev().enumerate() {
+    pub fn set_turn(&mut self, turn: u32) { self.turn = turn }
+    pub fn entries(&self) -> &Vec<LogEntry> { &self.entries }
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn update_logs(&mut self, message: LogMessage) {
+        self.entries.push(LogEntry { turn: self.turn, message });
+    }
+    //Turns the turn's events into the messages the player reads. The wording and colours come from the data files.
+    pub fn log_events(&mut self, events: &EventQueue, data: &GameData) {
+        for event in events.iter() {
+            if let Some(message) = describe_event(event, data) {
+                self.update_logs(message);
+            }
+        }
+    }
+
+    //The most recent messages for the sidebar, newest at the top and fading out as they get older
+    pub fn format(&self) -> TextBuilder {
+        let max_width = (LOG_BOX.width() * 2) as usize;
+        let mut lines: Vec<LogMessage> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if lines.len() >= SIDEBAR_LOG_LINES { break }
+            lines.append(&mut wrap_message(&entry.message, max_width));
+        }
+        lines.truncate(SIDEBAR_LOG_LINES);
+
+        let mut builder = TextBuilder::empty();
+        for (n, message) in lines.iter().enumerate() {
             for (i, part) in message.parts.iter().enumerate() {
                 let bg = message.colors[i].bg;
                 builder.bg(bg - RGBA::from_f32(0.01,0.01,0.01,0.0) * n as f32);
@@ -29,66 +102,163 @@ impl LogBufferTrait for LogBuffer {
 
         return builder;
     }
-    fn update_logs(&mut self, message: LogMessage) {
-        let max_width = (LOG_BOX.width() * 2) as usize;
-        let mut group_list: Vec<(String, ColorPair)> = Vec::new();
 
-        for (c, part) in message.parts.iter().enumerate() {
-            let split = part.split_whitespace();
-            for s in split.into_iter() {
-                group_list.push((s.to_string() + " ", message.colors[c]));
+    /// The history wrapped to `max_width`, oldest line first, with each message led by its turn.
+    /// Messages in any of the `hidden` categories are left out. The wrapped lines are kept between calls,
+    /// so asking again only wraps what's been added since, unless the width or hidden categories change.
+    pub fn history_lines(&self, max_width: usize, hidden: &[LogCategory]) -> Ref<Vec<LogMessage>> {
+        {
+            let mut cache = self.history.borrow_mut();
+            let same_view = cache.max_width == max_width
+                && cache.hidden.len() == hidden.len()
+                && hidden.iter().all(|c| cache.hidden.contains(c));
+            if !same_view || cache.wrapped > self.entries.len() {
+                *cache = HistoryCache { max_width, hidden: hidden.to_vec(), wrapped: 0, lines: Vec::new() };
             }
-        }
 
-        let mut final_messages: Vec<LogMessage> = Vec::new();
-        let mut wip_message = LogMessage::new();
-        let mut line_len: usize = 0;
-        let mut last_color: ColorPair = ColorPair::new(WHITE, GREY10);
-
-        for g in group_list.iter() {
-            if line_len + g.0.chars().count() > max_width {
-                while line_len < max_width {
-                    wip_message = wip_message.add_part(" ", last_color);
-                    line_len += 1;
-                }
-                final_messages.insert(0, wip_message);
-
-                line_len = g.0.len();
-                wip_message = LogMessage::new().add_part(g.0.to_string(), g.1);
+            let start = cache.wrapped;
+            for entry in self.entries[start..].iter().filter(|e| !hidden.contains(&e.message.category)) {
+                let mut tagged = LogMessage::new().category(entry.message.category).add_part(format!("[{:>4}]", entry.turn), ColorPair::new(GREY50, GREY10));
+                tagged.parts.extend(entry.message.parts.iter().cloned());
+                tagged.colors.extend(entry.message.colors.iter().cloned());
+                cache.lines.append(&mut wrap_message(&tagged, max_width));
             }
-            else {
-                wip_message = wip_message.add_part(g.0.to_string(), g.1);
-                line_len += g.0.chars().count();
+            cache.wrapped = self.entries.len();
+        }
+        return Ref::map(self.history.borrow(), |cache| &cache.lines)
+    }
+}
+
+//Splits a message into lines no wider than `max_width`, padding each one out so its background fills the line
+fn wrap_message(message: &LogMessage, max_width: usize) -> Vec<LogMessage> {
+    let mut group_list: Vec<(String, ColorPair)> = Vec::new();
+
+    for (c, part) in message.parts.iter().enumerate() {
+        let split = part.split_whitespace();
+        for s in split.into_iter() {
+            group_list.push((s.to_string() + " ", message.colors[c]));
+        }
+    }
+
+    let mut final_messages: Vec<LogMessage> = Vec::new();
+    let category = message.category;
+    let mut wip_message = LogMessage::new().category(category);
+    let mut line_len: usize = 0;
+    let mut last_color: ColorPair = ColorPair::new(WHITE, GREY10);
+
+    for g in group_list.iter() {
+        if line_len + g.0.chars().count() > max_width && line_len > 0 {
+            while line_len < max_width {
+                wip_message = wip_message.add_part(" ", last_color);
+                line_len += 1;
             }
-            last_color = g.1;
+            final_messages.push(wip_message);
+
+            line_len = g.0.chars().count();
+            wip_message = LogMessage::new().category(category).add_part(g.0.to_string(), g.1);
         }
-        while line_len < max_width {
-            wip_message = wip_message.add_part(" ", last_color);
-            line_len += 1;
+        else {
+            wip_message = wip_message.add_part(g.0.to_string(), g.1);
+            line_len += g.0.chars().count();
         }
-        final_messages.insert(0, wip_message);
+        last_color = g.1;
+    }
+    while line_len < max_width {
+        wip_message = wip_message.add_part(" ", last_color);
+        line_len += 1;
+    }
+    final_messages.push(wip_message);
+
+    return final_messages
+}
 
-        for message in final_messages.iter() {
-            self.push(LogMessage {
-                parts: message.parts.to_vec(),
-                colors: message.colors.to_vec()
-            });
+fn describe_event(event: &GameEvent, data: &GameData) -> Option<LogMessage> {
+    let text = ColorPair::new(WHITE, GREY10);
+    let color = |who: &Combatant| ColorPair::new(data.color_of(who), GREY10);
+
+    let message = match event {
+        GameEvent::DamageDealt { attacker, target, amount } => LogMessage::new()
+            .add_part(&attacker.name, color(attacker))
+            .add_part("attacks", text)
+            .add_part(&target.name, color(target))
+            .add_part(format!("for {} damage.", amount), text),
+        GameEvent::MemberSlain { victim, .. } => LogMessage::new()
+            .add_part(&victim.name, color(victim))
+            .add_part("has been slain.", text),
+        GameEvent::PartyDefeated { party } => LogMessage::new()
+            .add_part("You have defeated the", text)
+            .add_part(format!("{}.", party.name), color(party)),
+        GameEvent::PlayerPartyWiped { .. } => return None,
+        GameEvent::ElfRecruited { elf } => LogMessage::new()
+            .add_part(format!("{}, the {}", elf.name, elf.class), color(elf))
+            .add_part("has joined the party!", text),
+        GameEvent::PartyFull => LogMessage::new()
+            .add_part("Your party is full!", text),
+        GameEvent::AbilityOnCooldown { caster, ability } => LogMessage::new()
+            .add_part(format!("{}'s", caster.name), color(caster))
+            .add_part(format!("{} is still on cooldown!", ability), text),
+        GameEvent::AbilityFailed { reason } => LogMessage::new()
+            .add_part(match reason {
+                AbilityFailure::NeedsTarget => "That ability needs a target!",
+                AbilityFailure::InvalidTarget => "Why would you want to do that?",
+                AbilityFailure::OutOfRange => "Your target must be in melee range to use this ability!",
+                AbilityFailure::NoTrees => "There are no trees close enough to cut down!"
+            }, text),
+        GameEvent::AbilityUsed { caster, ability, target, amount, healing } => {
+            //Abilities without a message go off quietly
+            let mut message = LogMessage::new()
+                .add_part(&caster.name, color(caster))
+                .add_part(data.ability(ability)?.message.as_ref()?, text);
+            if let Some(target) = target {
+                message = message.add_part(&target.name, color(target));
+            }
+            if let Some(amount) = amount {
+                message = message
+                    .add_part("for", text)
+                    .add_part(amount, ColorPair::new(GOLD, GREY10))
+                    .add_part(if *healing { "HP." } else { "damage." }, text);
+            }
+            message
         }
-        if self.len() > 24 { self.remove(0); }
+        GameEvent::PortalEntered { depth } => LogMessage::new()
+            .add_part("You step through the portal, deeper into the forest.", text)
+            .add_part(format!("Depth {}, the {}.", depth, data.biome(*depth).name), ColorPair::new(CYAN, GREY10))
+            .add_part(&data.biome(*depth).arrival, ColorPair::new(GREY50, GREY10)),
+        GameEvent::ReturnPortalEntered { depth } => LogMessage::new()
+            .add_part("You fall back through the portal to regroup.", text)
+            .add_part(format!("Depth {}, the {}.", depth, data.biome(*depth).name), ColorPair::new(BLUE_VIOLET, GREY10)),
+    };
+
+    return Some(message.category(event_category(event)))
+}
+
+fn event_category(event: &GameEvent) -> LogCategory {
+    return match event {
+        GameEvent::DamageDealt { .. } | GameEvent::MemberSlain { .. } | GameEvent::PartyDefeated { .. } | GameEvent::PlayerPartyWiped { .. } => LogCategory::Combat,
+        GameEvent::ElfRecruited { .. } | GameEvent::PartyFull => LogCategory::Party,
+        GameEvent::AbilityUsed { .. } | GameEvent::AbilityFailed { .. } | GameEvent::AbilityOnCooldown { .. } => LogCategory::Ability,
+        GameEvent::PortalEntered { .. } | GameEvent::ReturnPortalEntered { .. } => LogCategory::System,
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LogMessage {
     pub parts: Vec<String>,
-    pub colors: Vec<ColorPair>
+    pub colors: Vec<ColorPair>,
+    pub category: LogCategory
 }
 impl LogMessage {
     pub fn new() -> LogMessage {
         LogMessage {
             parts: Vec::new(),
-            colors: Vec::new()
+            colors: Vec::new(),
+            category: LogCategory::System
         }
     }
+    pub fn category(mut self, category: LogCategory) -> Self {
+        self.category = category;
+        return self
+    }
     pub fn add_part<T: ToString>(mut self, part: T, color: ColorPair) -> Self {
         self.parts.push(part.to_string());
         self.colors.push(color);
@@ -1,93 +1,83 @@
 #![windows_subsystem = "windows"]
 mod state;
-mod map;
-mod camera;
 mod render;
-mod object;
 mod input;
-mod spawns;
-mod party;
-mod actions;
-mod systems;
-mod logs;
-mod ai;
 
 mod prelude {
-    pub use bracket_lib::prelude::*;
+    pub use elfball::prelude::*;
     pub use crate::state::*;
-    pub use crate::map::*;
-    pub use crate::camera::*;
     pub use crate::render::*;
-    pub use crate::object::*;
     pub use crate::input::*;
-    pub use crate::spawns::*;
-    pub use crate::party::*;
-    pub use crate::actions::*;
-    pub use crate::systems::*;
-    pub use crate::logs::*;
-    pub use crate::ai::*;
-
-    use std::cmp::Reverse;
-
-    pub const CONSOLE_W: i32 = 80;
-    pub const CONSOLE_H: i32 = 60;
+}
 
-    pub const MAP_CON: usize = 0;
-    pub const TEXT_CON: usize = 1;
+use crate::prelude::*;
+use std::rc::Rc;
 
-    //Constant point values used as directional indicators
-    pub const DL_LEFT: Point = Point { x: -1, y: 0 };
-    pub const DL_RIGHT: Point = Point { x: 1, y: 0 };
-    pub const DL_UP: Point = Point { x: 0, y: -1 };
-    pub const DL_DOWN: Point = Point { x: 0, y: 1 };
+embedded_resource!(TEXTFONT, "../res/text.png");
+embedded_resource!(MAPFONT, "../res/map.png");
+fn main() {
+    link_resource!(TEXTFONT, "res/text.png");
+    link_resource!(MAPFONT, "res/map.png");
 
-    //Type alias for a vec of array indices and initiative values
-    pub type InitList = Vec<(usize, u8)>;
+    let data = match GameData::load() {
+        Ok(data) => Rc::new(data),
+        Err(e) => {
+            eprintln!("Could not load the game data: {}", e);
+            std::process::exit(1)
+        }
+    };
 
-    pub trait InitListTrait {
-        fn add_object(&mut self, id: usize, init: u8);
-        fn sort(&mut self);
-    }
-    impl InitListTrait for InitList {
-        //Adds a new object to the list
-        fn add_object(&mut self, id: usize, init: u8) {
-            self.push((id, init));
+    let window = (data.config.window_width, data.config.window_height);
+    let args: Vec<String> = std::env::args().collect();
+    let state = if let Some(path) = parse_replay_arg(&args) {
+        match Replay::read_from_disk(&path) {
+            Ok(replay) => State::from_replay(replay, data),
+            Err(e) => {
+                eprintln!("Could not read the replay {}: {}", path, e);
+                std::process::exit(1)
+            }
         }
-        //Sorts by descending initiative order
-        fn sort(&mut self) {
-            self.sort_by_key(|a| Reverse(a.1));
+    } else if args.iter().any(|a| a == "--load") {
+        match Game::load_from_disk(SAVE_PATH, data) {
+            Ok(game) => State::from_game(game),
+            Err(e) => {
+                eprintln!("Could not resume the saved run: {}", e);
+                std::process::exit(1)
+            }
         }
-    }
+    } else {
+        State::init(parse_seed_arg(&args).unwrap_or_else(RunRng::random_seed), data)
+    };
 
-    pub trait Neighbor {
-        fn get_neighbors(&self) -> Vec<Point>;
+    match main_loop(build_console(window.0, window.1), state) {
+        Ok(_) => {}
+        Err(e) => panic!("Could not initialize due to a fatal error:\n{}", e),
     }
-    impl Neighbor for Point {
-        fn get_neighbors(&self) -> Vec<Point> {
-            return vec![
-                *self + DL_UP,
-                *self + DL_DOWN,
-                *self + DL_LEFT,
-                *self + DL_RIGHT,
-                *self + DL_UP + DL_LEFT,
-                *self + DL_UP + DL_RIGHT,
-                *self + DL_DOWN + DL_LEFT,
-                *self + DL_DOWN + DL_RIGHT
-            ]
+}
+
+//Looks for a "--seed <number>" argument, so that a run can be reproduced exactly
+fn parse_seed_arg(args: &Vec<String>) -> Option<u64> {
+    let flag = args.iter().position(|a| a == "--seed")?;
+
+    return match args.get(flag + 1).map(|s| s.parse::<u64>()) {
+        Some(Ok(seed)) => Some(seed),
+        _ => {
+            eprintln!("--seed expects a whole number, e.g. --seed 12345");
+            std::process::exit(1)
         }
     }
 }
-use crate::prelude::*;
 
-embedded_resource!(TEXTFONT, "../res/text.png");
-embedded_resource!(MAPFONT, "../res/map.png");
-fn main() {
-    link_resource!(TEXTFONT, "res/text.png");
-    link_resource!(MAPFONT, "res/map.png");
+//Looks for a "--replay <file>" argument, to play a recorded run back
+fn parse_replay_arg(args: &Vec<String>) -> Option<String> {
+    let flag = args.iter().position(|a| a == "--replay")?;
 
-    match main_loop(build_console(800, 640), State::init()) {
-        Ok(_) => {}
-        Err(e) => panic!("Could not initialize due to a fatal error:\n{}", e),
+    return match args.get(flag + 1) {
+        Some(path) => Some(path.clone()),
+        None => {
+            eprintln!("--replay expects the path of a replay file");
+            std::process::exit(1)
+        }
     }
 }
 
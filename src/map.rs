@@ -1,15 +1,53 @@
 use crate::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 
-#[derive(Clone,Copy,PartialEq)]
+/// What a single map tile is made of. Each kind decides how hard it is to cross, whether it can be seen through,
+/// and how it's drawn.
+#[derive(Clone,Copy,PartialEq,Serialize,Deserialize)]
 pub enum TileClass {
-    Tree, ForestFloor, ForestPortal
+    Tree, ForestFloor, ForestPortal, ReturnPortal,
+    Undergrowth, ShallowStream, DeepWater, FallenLog, StoneOutcrop, Clearing
 }
 impl TileClass {
-    pub fn does_collide(&self) -> bool { return *self == TileClass::Tree }
-    pub fn does_blos(&self) -> bool { return *self == TileClass::Tree }
+    //What it costs to step onto the tile, where open ground is 1. None means it can't be walked onto at all.
+    pub fn move_cost(&self) -> Option<f32> {
+        return match self {
+            TileClass::Tree | TileClass::DeepWater | TileClass::StoneOutcrop => None,
+            TileClass::ShallowStream => Some(2.0),
+            TileClass::FallenLog => Some(3.0),
+            TileClass::Undergrowth => Some(1.5),
+            TileClass::ForestFloor | TileClass::Clearing | TileClass::ForestPortal | TileClass::ReturnPortal => Some(1.0),
+        }
+    }
+    pub fn does_collide(&self) -> bool { return self.move_cost().is_none() }
+    //Blocks line of sight
+    pub fn does_blos(&self) -> bool {
+        return match self {
+            TileClass::Tree | TileClass::Undergrowth | TileClass::StoneOutcrop => true,
+            _ => false
+        }
+    }
+    pub fn is_portal(&self) -> bool { return *self == TileClass::ForestPortal || *self == TileClass::ReturnPortal }
+
+    //The glyph is always the same, but the colour comes from the biome's palette
+    pub fn render(&self, palette: &Palette) -> (FontCharType, ColorPair) {
+        return match self {
+            TileClass::ForestFloor => (46, ColorPair::new(palette.floor, BLACK)),
+            TileClass::Tree => (5, ColorPair::new(palette.tree, BLACK)),
+            TileClass::ForestPortal => (21, ColorPair::new(CYAN, BLACK)),
+            TileClass::ReturnPortal => (21, ColorPair::new(BLUE_VIOLET, BLACK)),
+            TileClass::Undergrowth => (34, ColorPair::new(palette.undergrowth, BLACK)),
+            TileClass::ShallowStream => (247, ColorPair::new(palette.shallow_stream, BLACK)),
+            TileClass::DeepWater => (247, ColorPair::new(palette.deep_water, BLACK)),
+            TileClass::FallenLog => (61, ColorPair::new(palette.fallen_log, BLACK)),
+            TileClass::StoneOutcrop => (30, ColorPair::new(palette.stone, BLACK)),
+            TileClass::Clearing => (44, ColorPair::new(palette.clearing, BLACK)),
+        }
+    }
 }
 
+/// One floor of the forest: its tiles, what the player has seen of it, and where things can be placed.
+#[derive(Serialize, Deserialize)]
 pub struct Map {
     pub width: i32,
     pub height: i32,
@@ -20,7 +58,9 @@ pub struct Map {
     pub visible: Vec<bool>,
     pub revealed: Vec<bool>,
     pub obj_blocked: Vec<bool>,
-    pub valid_spawns: Vec<Point>
+    pub valid_spawns: Vec<Point>,
+    //What prefabs stamped into the floor want spawned, and where. Used up when the floor is populated.
+    pub spawn_markers: Vec<(SpawnMarker, Point)>
 }
 impl Map {
     pub fn new(w: i32, h: i32) -> Map {
@@ -34,7 +74,8 @@ impl Map {
             visible: vec![false; (w * h) as usize],
             revealed: vec![false; (w * h) as usize],
             obj_blocked: vec![false; (w * h) as usize],
-            valid_spawns: Vec::new()
+            valid_spawns: Vec::new(),
+            spawn_markers: Vec::new()
         }
     }
 
@@ -48,13 +89,14 @@ impl Map {
         return self.in_bounds(pos) && !self.tiles[idx].does_collide() && !self.obj_blocked[idx]
     }
 
-    fn valid_exit(&self, pos: Point, delta: Point) -> Option<usize> {
+    //The tile a step lands on and what the terrain there costs to cross, if the step can be taken at all
+    fn valid_exit(&self, pos: Point, delta: Point) -> Option<(usize, f32)> {
         let dest = pos + delta;
 
         if self.in_bounds(dest) {
             if self.walkable(dest) {
                 let idx = self.point2d_to_index(dest);
-                return Some(idx)
+                return self.tiles[idx].move_cost().map(|cost| (idx, cost))
             }
             else {
                 return None
@@ -65,6 +107,89 @@ impl Map {
         }
     }
 
+    /// Flood-fills the open ground, 8 ways like the player walks, marking every tile reachable from `start`.
+    pub fn reachable_from(&self, start: Point) -> Vec<bool> {
+        let mut reached = vec![false; self.tiles.len()];
+        if !self.in_bounds(start) { return reached }
+
+        let start_idx = self.point2d_to_index(start);
+        let mut frontier = VecDeque::new();
+        reached[start_idx] = true;
+        frontier.push_back(start);
+
+        while let Some(pos) = frontier.pop_front() {
+            for d in [DL_LEFT, DL_RIGHT, DL_UP, DL_DOWN, DL_UP + DL_LEFT, DL_UP + DL_RIGHT, DL_DOWN + DL_LEFT, DL_DOWN + DL_RIGHT].iter() {
+                let next = pos + *d;
+                if !self.in_bounds(next) { continue }
+                let idx = self.point2d_to_index(next);
+                if !reached[idx] && !self.tiles[idx].does_collide() {
+                    reached[idx] = true;
+                    frontier.push_back(next);
+                }
+            }
+        }
+
+        return reached
+    }
+
+    /// Turns any open ground that can't be walked to from the start back into trees, so nothing can end up
+    /// somewhere it can never be reached. The biggest open area is the one kept rather than whatever the start
+    /// can reach: builders pick their start before they know how the ground joins up, and a start that lands in
+    /// a little pocket would otherwise throw away most of the floor. In that case the start moves to the nearest
+    /// tile of the biggest area instead.
+    pub fn cull_unreachable(&mut self) {
+        //Label every open area in one sweep, each tile getting the number of the area it belongs to
+        let mut region = vec![usize::MAX; self.tiles.len()];
+        let mut sizes: Vec<usize> = Vec::new();
+        for idx in 0..self.tiles.len() {
+            if region[idx] != usize::MAX || self.tiles[idx].does_collide() { continue }
+            let size = self.flood_region(idx, sizes.len(), &mut region);
+            sizes.push(size);
+        }
+        if sizes.is_empty() { return }
+
+        let largest = (0..sizes.len()).max_by_key(|r| (sizes[*r], usize::MAX - r)).unwrap();
+        let start_in_largest = self.in_bounds(self.starting_pos) && region[self.point2d_to_index(self.starting_pos)] == largest;
+        if !start_in_largest {
+            let start = self.starting_pos;
+            let nearest = (0..self.tiles.len())
+                .filter(|i| region[*i] == largest)
+                .min_by_key(|i| {
+                    let p = self.index_to_point2d(*i);
+                    (p.x - start.x).pow(2) + (p.y - start.y).pow(2)
+                })
+                .unwrap();
+            self.starting_pos = self.index_to_point2d(nearest);
+        }
+
+        for (idx, r) in region.iter().enumerate() {
+            if *r != largest && !self.tiles[idx].does_collide() { self.tiles[idx] = TileClass::Tree }
+        }
+    }
+
+    //Gives every open tile joined up with `start_idx` the label `label`, and hands back how many there were
+    fn flood_region(&self, start_idx: usize, label: usize, region: &mut Vec<usize>) -> usize {
+        let mut frontier = VecDeque::new();
+        region[start_idx] = label;
+        frontier.push_back(self.index_to_point2d(start_idx));
+        let mut size = 1;
+
+        while let Some(pos) = frontier.pop_front() {
+            for d in [DL_LEFT, DL_RIGHT, DL_UP, DL_DOWN, DL_UP + DL_LEFT, DL_UP + DL_RIGHT, DL_DOWN + DL_LEFT, DL_DOWN + DL_RIGHT].iter() {
+                let next = pos + *d;
+                if !self.in_bounds(next) { continue }
+                let idx = self.point2d_to_index(next);
+                if region[idx] == usize::MAX && !self.tiles[idx].does_collide() {
+                    region[idx] = label;
+                    size += 1;
+                    frontier.push_back(next);
+                }
+            }
+        }
+
+        return size
+    }
+
     pub fn get_valid_spawn_points(&mut self) {
         let mut points = Vec::new();
 
@@ -82,19 +207,19 @@ impl Map {
 }
 
 impl BaseMap for Map {
-    fn is_opaque(&self, idx: usize) -> bool { return self.tiles[idx] != TileClass::ForestFloor }
+    fn is_opaque(&self, idx: usize) -> bool { return self.tiles[idx].does_blos() }
     fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize,f32); 10]> {
         let mut exits = SmallVec::new();
         let pos = self.index_to_point2d(idx);
 
-        if let Some(idx) = self.valid_exit(pos, DL_LEFT) { exits.push((idx, 1.0)) }
-        if let Some(idx) = self.valid_exit(pos, DL_RIGHT) { exits.push((idx, 1.0)) }
-        if let Some(idx) = self.valid_exit(pos, DL_UP) { exits.push((idx, 1.0)) }
-        if let Some(idx) = self.valid_exit(pos, DL_DOWN) { exits.push((idx, 1.0)) }
-        if let Some(idx) = self.valid_exit(pos, DL_UP + DL_LEFT) { exits.push((idx, 1.45)) }
-        if let Some(idx) = self.valid_exit(pos, DL_DOWN + DL_LEFT) { exits.push((idx, 1.45)) }
-        if let Some(idx) = self.valid_exit(pos, DL_UP + DL_RIGHT) { exits.push((idx, 1.45)) }
-        if let Some(idx) = self.valid_exit(pos, DL_DOWN + DL_RIGHT) { exits.push((idx, 1.45)) }
+        if let Some((idx, cost)) = self.valid_exit(pos, DL_LEFT) { exits.push((idx, cost)) }
+        if let Some((idx, cost)) = self.valid_exit(pos, DL_RIGHT) { exits.push((idx, cost)) }
+        if let Some((idx, cost)) = self.valid_exit(pos, DL_UP) { exits.push((idx, cost)) }
+        if let Some((idx, cost)) = self.valid_exit(pos, DL_DOWN) { exits.push((idx, cost)) }
+        if let Some((idx, cost)) = self.valid_exit(pos, DL_UP + DL_LEFT) { exits.push((idx, cost * 1.45)) }
+        if let Some((idx, cost)) = self.valid_exit(pos, DL_DOWN + DL_LEFT) { exits.push((idx, cost * 1.45)) }
+        if let Some((idx, cost)) = self.valid_exit(pos, DL_UP + DL_RIGHT) { exits.push((idx, cost * 1.45)) }
+        if let Some((idx, cost)) = self.valid_exit(pos, DL_DOWN + DL_RIGHT) { exits.push((idx, cost * 1.45)) }
 
         return exits
     }
@@ -103,89 +228,3 @@ impl Algorithm2D for Map {
     fn dimensions(&self) -> Point { Point::new(self.width, self.height) }
 }
 
-
-pub fn cellular_automata_builder(w: i32, h: i32, start_mid: bool) -> Map {
-    let mut map = Map::new(w, h);
-    let mut rng = RandomNumberGenerator::new();
-
-    //Generate a random mishmash of walls and floors
-    for y in 1..map.height-1 {
-        for x in 1..map.width-1 {
-            let pos = Point::new(x,y);
-            let idx = map.point2d_to_index(pos);
-            let d100 = rng.roll_dice(1, 100);
-            if d100 > 25 { map.tiles[idx] = TileClass::ForestFloor; }
-        }
-    }
-
-    //Run the cellular automata algorithm against it
-    for _ in 0..23 {
-        let mut new_tilemap = map.tiles.to_vec();
-        for y in 1..map.height-1 {
-            for x in 1..map.width-1 {
-                let pos = Point::new(x,y);
-                let idx = map.point2d_to_index(pos);
-
-                let neighbors = get_neighbors_count(&map, idx);
-                if neighbors > 4 || neighbors == 0 { new_tilemap[idx] = TileClass::Tree }
-                else { new_tilemap[idx] = TileClass::ForestFloor }
-            }
-        }
-        map.tiles = new_tilemap.to_vec();
-    }
-
-    //If the starting position should be the middle of the map
-    if start_mid {
-        let mut pos = Point::new(map.width/2, map.height/2);
-        let mut idx = map.point2d_to_index(pos);
-
-        while map.tiles[idx] != TileClass::ForestFloor {
-            pos.x -= 1;
-            idx = map.point2d_to_index(pos);
-        }
-        map.starting_pos = pos;
-    }
-    //Otherwise, start at the furthest point from the middle
-    else {
-        let start_pt = Point::new(map.width/2, map.height/2);
-        map.starting_pos = find_furthest_point(&mut map, &start_pt);
-    }
-
-    //Set the starting area on the map
-    map.starting_area = Rect::with_size(&map.starting_pos.x - 4, &map.starting_pos.y - 4, 9, 9).point_set();
-    map.get_valid_spawn_points();
-
-    //Set up where the exit portal will be located
-    let start_pt = map.starting_pos.clone();
-    map.exit_pos = find_furthest_point(&mut map, &start_pt);
-    let exit_idx = map.point2d_to_index(map.exit_pos);
-    map.tiles[exit_idx] = TileClass::ForestPortal;
-
-    return map
-}
-fn get_neighbors_count(map: &Map, idx: usize) -> u8 {
-    let mut final_val = 0;
-    if map.tiles[idx - 1] == TileClass::Tree { final_val += 1 }
-    if map.tiles[idx + 1] == TileClass::Tree { final_val += 1 }
-    if map.tiles[idx - map.width as usize] == TileClass::Tree { final_val += 1 }
-    if map.tiles[idx + map.width as usize] == TileClass::Tree { final_val += 1 }
-    if map.tiles[idx - (map.width as usize - 1)] == TileClass::Tree { final_val += 1 }
-    if map.tiles[idx + (map.width as usize - 1)] == TileClass::Tree { final_val += 1 }
-    if map.tiles[idx - (map.width as usize + 1)] == TileClass::Tree { final_val += 1 }
-    if map.tiles[idx + (map.width as usize + 1)] == TileClass::Tree { final_val += 1 }
-    return final_val
-}
-
-fn find_furthest_point(map: &mut Map, pos: &Point) -> Point {
-    let start_idx = vec![map.point2d_to_index(*pos)];
-    let dijkstra_map = DijkstraMap::new(map.width, map.height, &start_idx, map, 8192.0);
-
-    let start_tile = dijkstra_map.map
-        .iter()
-        .enumerate()
-        .filter(|(_,dist)| *dist < &f32::MAX)
-        .max_by(|a,b| a.1.partial_cmp(b.1).unwrap())
-        .unwrap().0;
-
-    return map.index_to_point2d(start_tile)
-}
\ No newline at end of file
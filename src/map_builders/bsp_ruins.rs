@@ -0,0 +1,96 @@
+use crate::prelude::*;
+use super::{finish_map, nearest_open_tile};
+
+/// The ruins of an old elven city: open ground carved up by binary space partitioning into lots, most of them
+/// holding the stone walls of a building, overgrown and with gaps crumbled through them. The rest are left as
+/// plazas the forest has taken back, strewn with fallen logs.
+pub struct BspRuinsBuilder {
+    //Lots are never split smaller than this in either direction
+    min_lot: i32,
+    //Chance out of 100 that a lot has a building on it
+    building_chance: i32,
+    terrain: TerrainMix
+}
+impl BspRuinsBuilder {
+    pub fn new() -> BspRuinsBuilder { BspRuinsBuilder { min_lot: 7, building_chance: 70, terrain: TerrainMix::default() } }
+    pub fn with_terrain(mut self, terrain: TerrainMix) -> BspRuinsBuilder { self.terrain = terrain; self }
+}
+impl MapBuilder for BspRuinsBuilder {
+    fn build(&mut self, w: i32, h: i32, rng: &mut RandomNumberGenerator) -> Map {
+        let mut map = Map::new(w, h);
+        for y in 1..h-1 {
+            for x in 1..w-1 {
+                let idx = map.point2d_to_index(Point::new(x, y));
+                map.tiles[idx] = TileClass::ForestFloor;
+            }
+        }
+
+        let mut lots = Vec::new();
+        self.split_lot(Rect::with_size(2, 2, w - 5, h - 5), rng, &mut lots);
+
+        for lot in lots.iter() {
+            //Leave a street around each lot
+            let inner = Rect::with_exact(lot.x1 + 1, lot.y1 + 1, lot.x2 - 1, lot.y2 - 1);
+            if rng.roll_dice(1, 100) <= self.building_chance {
+                build_ruin(&mut map, inner, rng);
+            } else {
+                //A plaza the forest has taken back
+                inner.for_each(|p| {
+                    let idx = map.point2d_to_index(p);
+                    map.tiles[idx] = match rng.roll_dice(1, 100) {
+                        roll if roll <= 15 => TileClass::Tree,
+                        roll if roll <= 15 + self.terrain.fallen_logs => TileClass::FallenLog,
+                        _ => TileClass::Clearing
+                    };
+                });
+            }
+        }
+
+        map.starting_pos = nearest_open_tile(&map, Point::new(w / 2, h / 2));
+        finish_map(&mut map);
+        return map
+    }
+}
+impl BspRuinsBuilder {
+    //Keeps halving a lot along its longer side until the pieces get too small to split
+    fn split_lot(&self, lot: Rect, rng: &mut RandomNumberGenerator, lots: &mut Vec<Rect>) {
+        let (width, height) = (lot.width(), lot.height());
+        let can_split_x = width >= self.min_lot * 2;
+        let can_split_y = height >= self.min_lot * 2;
+
+        if can_split_x && (width >= height || !can_split_y) {
+            let at = rng.range(lot.x1 + self.min_lot, lot.x2 - self.min_lot + 1);
+            self.split_lot(Rect::with_exact(lot.x1, lot.y1, at, lot.y2), rng, lots);
+            self.split_lot(Rect::with_exact(at, lot.y1, lot.x2, lot.y2), rng, lots);
+        } else if can_split_y {
+            let at = rng.range(lot.y1 + self.min_lot, lot.y2 - self.min_lot + 1);
+            self.split_lot(Rect::with_exact(lot.x1, lot.y1, lot.x2, at), rng, lots);
+            self.split_lot(Rect::with_exact(lot.x1, at, lot.x2, lot.y2), rng, lots);
+        } else {
+            lots.push(lot);
+        }
+    }
+}
+
+//Raises the walls of a ruined building around the edge of `rect`, with a doorway through every side
+//and a few more gaps where they've fallen in
+fn build_ruin(map: &mut Map, rect: Rect, rng: &mut RandomNumberGenerator) {
+    let doors = [
+        Point::new(rng.range(rect.x1 + 1, rect.x2), rect.y1),
+        Point::new(rng.range(rect.x1 + 1, rect.x2), rect.y2),
+        Point::new(rect.x1, rng.range(rect.y1 + 1, rect.y2)),
+        Point::new(rect.x2, rng.range(rect.y1 + 1, rect.y2)),
+    ];
+
+    for y in rect.y1..=rect.y2 {
+        for x in rect.x1..=rect.x2 {
+            let on_wall = x == rect.x1 || x == rect.x2 || y == rect.y1 || y == rect.y2;
+            let pos = Point::new(x, y);
+            if on_wall && !doors.contains(&pos) && rng.roll_dice(1, 100) <= 80 {
+                let idx = map.point2d_to_index(pos);
+                //Most of the old stonework still stands, but trees have pushed up through some of it
+                map.tiles[idx] = if rng.roll_dice(1, 100) <= 75 { TileClass::StoneOutcrop } else { TileClass::Tree };
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::prelude::*;
+use super::{finish_map, find_furthest_point, carve_stream};
+
+/// Grows a forest floor with cellular automata: dense woods broken up by winding open ground, with undergrowth
+/// crowding in along the treeline and sometimes a stream running through.
+/// `start_mid` puts the starting position near the middle of the map, otherwise it's as far from the middle as possible.
+pub struct CellularAutomataBuilder {
+    start_mid: bool,
+    terrain: TerrainMix
+}
+impl CellularAutomataBuilder {
+    pub fn new(start_mid: bool) -> CellularAutomataBuilder { CellularAutomataBuilder { start_mid, terrain: TerrainMix::default() } }
+    pub fn with_terrain(mut self, terrain: TerrainMix) -> CellularAutomataBuilder { self.terrain = terrain; self }
+}
+impl MapBuilder for CellularAutomataBuilder {
+    fn build(&mut self, w: i32, h: i32, rng: &mut RandomNumberGenerator) -> Map {
+        let mut map = Map::new(w, h);
+
+        //Generate a random mishmash of walls and floors
+        for y in 1..map.height-1 {
+            for x in 1..map.width-1 {
+                let pos = Point::new(x,y);
+                let idx = map.point2d_to_index(pos);
+                let d100 = rng.roll_dice(1, 100);
+                if d100 > self.terrain.tree_density { map.tiles[idx] = TileClass::ForestFloor; }
+            }
+        }
+
+        //Run the cellular automata algorithm against it
+        for _ in 0..self.terrain.iterations {
+            let mut new_tilemap = map.tiles.to_vec();
+            for y in 1..map.height-1 {
+                for x in 1..map.width-1 {
+                    let pos = Point::new(x,y);
+                    let idx = map.point2d_to_index(pos);
+
+                    let neighbors = get_neighbors_count(&map, idx);
+                    if neighbors > 4 || neighbors == 0 { new_tilemap[idx] = TileClass::Tree }
+                    else { new_tilemap[idx] = TileClass::ForestFloor }
+                }
+            }
+            map.tiles = new_tilemap.to_vec();
+        }
+
+        //Let undergrowth creep out of the trees into the open ground beside them
+        let mut new_tilemap = map.tiles.to_vec();
+        for y in 1..map.height-1 {
+            for x in 1..map.width-1 {
+                let idx = map.point2d_to_index(Point::new(x,y));
+                if map.tiles[idx] == TileClass::ForestFloor && get_neighbors_count(&map, idx) >= 3 && rng.roll_dice(1, 100) <= self.terrain.undergrowth {
+                    new_tilemap[idx] = TileClass::Undergrowth;
+                }
+            }
+        }
+        map.tiles = new_tilemap;
+        if rng.roll_dice(1, 100) <= self.terrain.stream { carve_stream(&mut map, self.terrain.deep_water, rng); }
+
+        //If the starting position should be the middle of the map
+        if self.start_mid {
+            let mut pos = Point::new(map.width/2, map.height/2);
+            let mut idx = map.point2d_to_index(pos);
+
+            while map.tiles[idx].does_collide() {
+                pos.x -= 1;
+                idx = map.point2d_to_index(pos);
+            }
+            map.starting_pos = pos;
+        }
+        //Otherwise, start at the furthest point from the middle
+        else {
+            let start_pt = Point::new(map.width/2, map.height/2);
+            map.starting_pos = find_furthest_point(&mut map, &start_pt);
+        }
+
+        finish_map(&mut map);
+        return map
+    }
+}
+
+fn get_neighbors_count(map: &Map, idx: usize) -> u8 {
+    let mut final_val = 0;
+    if map.tiles[idx - 1] == TileClass::Tree { final_val += 1 }
+    if map.tiles[idx + 1] == TileClass::Tree { final_val += 1 }
+    if map.tiles[idx - map.width as usize] == TileClass::Tree { final_val += 1 }
+    if map.tiles[idx + map.width as usize] == TileClass::Tree { final_val += 1 }
+    if map.tiles[idx - (map.width as usize - 1)] == TileClass::Tree { final_val += 1 }
+    if map.tiles[idx + (map.width as usize - 1)] == TileClass::Tree { final_val += 1 }
+    if map.tiles[idx - (map.width as usize + 1)] == TileClass::Tree { final_val += 1 }
+    if map.tiles[idx + (map.width as usize + 1)] == TileClass::Tree { final_val += 1 }
+    return final_val
+}
@@ -0,0 +1,59 @@
+use crate::prelude::*;
+use super::finish_map;
+
+/// Wanders a string of drunken walkers out from the middle of a solid wood, leaving a network of narrow trails.
+/// Every walker after the first sets off from ground that's already been cleared, so the trails all join up.
+/// Fallen logs lie across the trails here and there.
+pub struct DrunkardsWalkBuilder {
+    //How much of the map should end up open, from 0 to 1
+    floor_target: f32,
+    //How many steps each walker takes before the next one sets off
+    walk_length: u32,
+    terrain: TerrainMix
+}
+impl DrunkardsWalkBuilder {
+    pub fn new() -> DrunkardsWalkBuilder { DrunkardsWalkBuilder { floor_target: 0.4, walk_length: 200, terrain: TerrainMix::default() } }
+    pub fn with_terrain(mut self, terrain: TerrainMix) -> DrunkardsWalkBuilder { self.terrain = terrain; self }
+}
+impl MapBuilder for DrunkardsWalkBuilder {
+    fn build(&mut self, w: i32, h: i32, rng: &mut RandomNumberGenerator) -> Map {
+        let mut map = Map::new(w, h);
+        let center = Point::new(w / 2, h / 2);
+        let target = (((w - 2) * (h - 2)) as f32 * self.floor_target) as usize;
+
+        let mut open: Vec<usize> = Vec::new();
+        let mut walkers = 0;
+        //The walker limit is only there in case a tiny map can never reach the target
+        while open.len() < target && walkers < 500 {
+            let mut pos = if open.is_empty() { center } else { map.index_to_point2d(open[rng.range(0, open.len())]) };
+
+            for _ in 0..self.walk_length {
+                let idx = map.point2d_to_index(pos);
+                if map.tiles[idx] == TileClass::Tree {
+                    map.tiles[idx] = TileClass::ForestFloor;
+                    open.push(idx);
+                }
+
+                let step = match rng.range(0, 4) {
+                    0 => DL_LEFT,
+                    1 => DL_RIGHT,
+                    2 => DL_UP,
+                    _ => DL_DOWN
+                };
+                let next = pos + step;
+                //Keep a ring of trees around the edge
+                if next.x >= 1 && next.x < w - 1 && next.y >= 1 && next.y < h - 1 { pos = next }
+            }
+            walkers += 1;
+        }
+
+        //Now and then a tree has come down across a trail
+        for idx in open.iter() {
+            if rng.roll_dice(1, 100) <= self.terrain.fallen_logs { map.tiles[*idx] = TileClass::FallenLog; }
+        }
+
+        map.starting_pos = center;
+        finish_map(&mut map);
+        return map
+    }
+}
@@ -0,0 +1,96 @@
+mod cellular;
+mod drunkard;
+mod voronoi;
+mod bsp_ruins;
+mod prefabs;
+
+pub use cellular::*;
+pub use drunkard::*;
+pub use voronoi::*;
+pub use bsp_ruins::*;
+pub use prefabs::*;
+
+use crate::prelude::*;
+
+/// Something that can grow a floor of the forest. Whatever it builds comes back with `starting_pos`,
+/// `exit_pos` (holding the portal) and `valid_spawns` filled in.
+pub trait MapBuilder {
+    fn build(&mut self, width: i32, height: i32, rng: &mut RandomNumberGenerator) -> Map;
+}
+
+/// Picks a generator for a depth and builds a floor with it, grown with the terrain mix of the depth's biome.
+/// The first floor is always the cellular forest, below that every floor rolls for one so they stop all looking
+/// alike. Any prefabs that come up for the depth are stamped in once it's built.
+pub fn build_floor(depth: u32, width: i32, height: i32, terrain: &TerrainMix, prefabs: &[PrefabDef], rng: &mut RandomNumberGenerator) -> Map {
+    let terrain = terrain.clone();
+    let mut builder: Box<dyn MapBuilder> = if depth <= 1 {
+        Box::new(CellularAutomataBuilder::new(true).with_terrain(terrain))
+    } else {
+        match rng.range(0, 4) {
+            0 => Box::new(CellularAutomataBuilder::new(true).with_terrain(terrain)),
+            1 => Box::new(DrunkardsWalkBuilder::new().with_terrain(terrain)),
+            2 => Box::new(VoronoiGroveBuilder::new().with_terrain(terrain)),
+            _ => Box::new(BspRuinsBuilder::new().with_terrain(terrain))
+        }
+    };
+    let mut map = builder.build(width, height, rng);
+    stamp_prefabs(&mut map, depth, prefabs, rng);
+    return map
+}
+
+//Everything the builders share once the tiles are laid out: clearing out pockets that can't be walked to, then
+//the starting area, spawn points and the portal out, placed as far from the start as can be walked
+fn finish_map(map: &mut Map) {
+    map.cull_unreachable();
+    map.starting_area = Rect::with_size(&map.starting_pos.x - 4, &map.starting_pos.y - 4, 9, 9).point_set();
+    map.get_valid_spawn_points();
+
+    let start_pt = map.starting_pos.clone();
+    map.exit_pos = find_furthest_point(map, &start_pt);
+    let exit_idx = map.point2d_to_index(map.exit_pos);
+    map.tiles[exit_idx] = TileClass::ForestPortal;
+}
+
+//Runs a stream from the top of the map to the bottom: shallow along the banks, with a channel down the middle
+//that's too deep to wade `deep_water` percent of the way
+fn carve_stream(map: &mut Map, deep_water: i32, rng: &mut RandomNumberGenerator) {
+    let mut x = rng.range(map.width / 4, map.width * 3 / 4);
+    for y in 1..map.height-1 {
+        for dx in -1..=1 {
+            let pos = Point::new(x + dx, y);
+            if pos.x < 1 || pos.x >= map.width - 1 { continue }
+            let idx = map.point2d_to_index(pos);
+            let deep = dx == 0 && rng.roll_dice(1, 100) <= deep_water;
+            map.tiles[idx] = if deep { TileClass::DeepWater } else { TileClass::ShallowStream };
+        }
+        x = (x + rng.range(-1, 2)).max(2).min(map.width - 3);
+    }
+}
+
+fn find_furthest_point(map: &mut Map, pos: &Point) -> Point {
+    let start_idx = vec![map.point2d_to_index(*pos)];
+    let dijkstra_map = DijkstraMap::new(map.width, map.height, &start_idx, map, 8192.0);
+
+    let start_tile = dijkstra_map.map
+        .iter()
+        .enumerate()
+        .filter(|(_,dist)| *dist < &f32::MAX)
+        .max_by(|a,b| a.1.partial_cmp(b.1).unwrap())
+        .unwrap().0;
+
+    return map.index_to_point2d(start_tile)
+}
+
+//The open tile closest to a point, for builders that don't naturally leave one right where they want to start
+fn nearest_open_tile(map: &Map, pos: Point) -> Point {
+    let idx = map.tiles.iter()
+        .enumerate()
+        .filter(|(_, t)| !t.does_collide())
+        .min_by_key(|(i, _)| {
+            let p = map.index_to_point2d(*i);
+            (p.x - pos.x).pow(2) + (p.y - pos.y).pow(2)
+        })
+        .map_or(map.point2d_to_index(pos), |(i, _)| i);
+
+    return map.index_to_point2d(idx)
+}
@@ -0,0 +1,176 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+//How many spots to try for a prefab before giving up on it for this floor
+const PREFAB_PLACEMENT_TRIES: u32 = 40;
+
+/// Something a prefab asks to have spawned on one of its tiles. There are no items in the game yet, so item
+/// markers get placed but nothing is spawned for them.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SpawnMarker { Band, Beast, Elf, Item }
+
+/// A small hand-drawn set piece, as written in the prefabs file, that gets stamped into generated floors.
+#[derive(Clone, Deserialize)]
+pub struct PrefabDef {
+    pub name: String,
+    //The first and last depth it can turn up on. Leaving out the last one covers every depth from there down.
+    pub from: u32,
+    #[serde(default)]
+    pub to: Option<u32>,
+    //Chance out of 100 that a floor it can turn up on gets one
+    pub chance: i32,
+    //The drawing itself, one string per row, all the same width
+    pub rows: Vec<String>
+}
+impl PrefabDef {
+    pub fn covers(&self, depth: u32) -> bool { depth >= self.from && self.to.map_or(true, |to| depth <= to) }
+    pub fn width(&self) -> i32 { self.rows.first().map_or(0, |r| r.chars().count() as i32) }
+    pub fn height(&self) -> i32 { self.rows.len() as i32 }
+
+    //Every character of the drawing that means something, by its offset from the top left corner
+    fn cells(&self) -> Vec<(Point, PrefabCell)> {
+        let mut cells = Vec::new();
+        for (y, row) in self.rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if let Some(cell) = prefab_cell(c) { cells.push((Point::new(x as i32, y as i32), cell)); }
+            }
+        }
+        return cells
+    }
+}
+
+/// What one character of a prefab drawing stands for.
+pub enum PrefabCell {
+    //A space leaves whatever was generated there alone, so prefabs don't have to be rectangles
+    Keep,
+    Tile(TileClass),
+    //Spawns something, standing on open ground
+    Marker(SpawnMarker)
+}
+impl PrefabCell {
+    fn tile(&self) -> Option<TileClass> {
+        return match self {
+            PrefabCell::Keep => None,
+            PrefabCell::Tile(tile) => Some(*tile),
+            PrefabCell::Marker(_) => Some(TileClass::ForestFloor)
+        }
+    }
+    pub fn is_open(&self) -> bool { return self.tile().map_or(false, |t| !t.does_collide()) }
+}
+
+/// The legend for prefab drawings. None means the character isn't in it.
+pub fn prefab_cell(c: char) -> Option<PrefabCell> {
+    return match c {
+        ' ' => Some(PrefabCell::Keep),
+        '#' => Some(PrefabCell::Tile(TileClass::Tree)),
+        '.' => Some(PrefabCell::Tile(TileClass::ForestFloor)),
+        ',' => Some(PrefabCell::Tile(TileClass::Undergrowth)),
+        '~' => Some(PrefabCell::Tile(TileClass::ShallowStream)),
+        '=' => Some(PrefabCell::Tile(TileClass::DeepWater)),
+        '-' => Some(PrefabCell::Tile(TileClass::FallenLog)),
+        '^' => Some(PrefabCell::Tile(TileClass::StoneOutcrop)),
+        '_' => Some(PrefabCell::Tile(TileClass::Clearing)),
+        'f' => Some(PrefabCell::Marker(SpawnMarker::Band)),
+        'B' => Some(PrefabCell::Marker(SpawnMarker::Beast)),
+        'e' => Some(PrefabCell::Marker(SpawnMarker::Elf)),
+        '!' => Some(PrefabCell::Marker(SpawnMarker::Item)),
+        _ => None
+    }
+}
+
+//Checks each drawing only uses the legend and has somewhere to stand. Whether it fits the configured map size is
+//checked once the config is loaded.
+pub fn validate_prefabs(prefabs: &Vec<PrefabDef>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for prefab in prefabs.iter() {
+        if prefab.name.trim().is_empty() {
+            return Err(String::from("a prefab is missing its name"))
+        }
+        if !seen.insert(prefab.name.as_str()) {
+            return Err(format!("the prefab \"{}\" is defined more than once", prefab.name))
+        }
+        if prefab.from == 0 {
+            return Err(format!("the prefab \"{}\" starts at depth 0, but the first floor is depth 1", prefab.name))
+        }
+        if prefab.to.map_or(false, |to| to < prefab.from) {
+            return Err(format!("the prefab \"{}\" ends at depth {}, before it starts", prefab.name, prefab.to.unwrap()))
+        }
+        if prefab.chance < 1 || prefab.chance > 100 {
+            return Err(format!("the prefab \"{}\" has a chance of {}, it has to be between 1 and 100", prefab.name, prefab.chance))
+        }
+        if prefab.rows.is_empty() || prefab.width() == 0 {
+            return Err(format!("the prefab \"{}\" has nothing drawn in it", prefab.name))
+        }
+        for (y, row) in prefab.rows.iter().enumerate() {
+            if row.chars().count() as i32 != prefab.width() {
+                return Err(format!("row {} of the prefab \"{}\" is {} wide, but the first row is {}", y + 1, prefab.name, row.chars().count(), prefab.width()))
+            }
+            for c in row.chars() {
+                if prefab_cell(c).is_none() {
+                    return Err(format!("the prefab \"{}\" has a '{}', which isn't in the legend", prefab.name, c))
+                }
+            }
+        }
+        if !prefab.rows.iter().any(|row| row.chars().any(|c| prefab_cell(c).map_or(false, |cell| cell.is_open()))) {
+            return Err(format!("the prefab \"{}\" has no open ground to walk into", prefab.name))
+        }
+    }
+
+    return Ok(())
+}
+
+/// Rolls for every prefab that can turn up at this depth and stamps the ones that come up into a finished map.
+/// A prefab only goes where it won't cut anything off: all the ground that could be walked to before still can
+/// be afterwards, and so can every open tile of the prefab. What it spawns ends up in `spawn_markers`, and none
+/// of its tiles are left as random spawn points.
+pub fn stamp_prefabs(map: &mut Map, depth: u32, prefabs: &[PrefabDef], rng: &mut RandomNumberGenerator) {
+    let mut taken: HashSet<Point> = HashSet::new();
+    for prefab in prefabs.iter().filter(|p| p.covers(depth)) {
+        if rng.roll_dice(1, 100) > prefab.chance { continue }
+        //Keep the ring of trees around the edge
+        if prefab.width() > map.width - 2 || prefab.height() > map.height - 2 { continue }
+
+        for _ in 0..PREFAB_PLACEMENT_TRIES {
+            let corner = Point::new(rng.range(1, map.width - prefab.width()), rng.range(1, map.height - prefab.height()));
+            if try_stamp(map, prefab, corner, &mut taken) { break }
+        }
+    }
+}
+
+//Stamps a prefab with its top left corner at `corner`, as long as that doesn't cut anything off or land on the
+//start, the portal or another prefab. Leaves the map untouched and returns false if it can't.
+fn try_stamp(map: &mut Map, prefab: &PrefabDef, corner: Point, taken: &mut HashSet<Point>) -> bool {
+    let cells: Vec<(Point, PrefabCell)> = prefab.cells()
+        .into_iter()
+        .filter(|(_, cell)| cell.tile().is_some())
+        .map(|(offset, cell)| (corner + offset, cell))
+        .collect();
+    let footprint: HashSet<Point> = cells.iter().map(|(pos, _)| *pos).collect();
+    if footprint.iter().any(|p| map.starting_area.contains(p) || *p == map.starting_pos || *p == map.exit_pos || taken.contains(p)) {
+        return false
+    }
+
+    let before = map.reachable_from(map.starting_pos);
+    let old_tiles = map.tiles.clone();
+    for (pos, cell) in cells.iter() {
+        let idx = map.point2d_to_index(*pos);
+        map.tiles[idx] = cell.tile().unwrap();
+    }
+
+    let after = map.reachable_from(map.starting_pos);
+    let connected = (0..map.tiles.len()).all(|idx| {
+        if footprint.contains(&map.index_to_point2d(idx)) { map.tiles[idx].does_collide() || after[idx] }
+        else { !before[idx] || after[idx] }
+    });
+    if !connected {
+        map.tiles = old_tiles;
+        return false
+    }
+
+    for (pos, cell) in cells.iter() {
+        if let PrefabCell::Marker(marker) = cell { map.spawn_markers.push((*marker, *pos)); }
+    }
+    map.valid_spawns.retain(|p| !footprint.contains(p));
+    taken.extend(footprint);
+    return true
+}
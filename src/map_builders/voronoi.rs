@@ -0,0 +1,62 @@
+use crate::prelude::*;
+use super::{finish_map, nearest_open_tile, carve_stream};
+
+/// Splits the forest into Voronoi cells, each one either a thick grove or an open clearing.
+/// Paths run along the seams between cells, so every clearing can be reached along them. Groves grow undergrowth
+/// at their edges, and a stream may cut across the whole thing.
+pub struct VoronoiGroveBuilder {
+    //Roughly how many tiles each cell covers
+    cell_area: i32,
+    //Chance out of 100 that a cell is a clearing rather than a grove
+    clearing_chance: i32,
+    terrain: TerrainMix
+}
+impl VoronoiGroveBuilder {
+    pub fn new() -> VoronoiGroveBuilder { VoronoiGroveBuilder { cell_area: 96, clearing_chance: 40, terrain: TerrainMix::default() } }
+    pub fn with_terrain(mut self, terrain: TerrainMix) -> VoronoiGroveBuilder { self.terrain = terrain; self }
+}
+impl MapBuilder for VoronoiGroveBuilder {
+    fn build(&mut self, w: i32, h: i32, rng: &mut RandomNumberGenerator) -> Map {
+        let mut map = Map::new(w, h);
+
+        let num_seeds = ((w * h) / self.cell_area).max(4);
+        let seeds: Vec<(Point, bool)> = (0..num_seeds)
+            .map(|_| (Point::new(rng.range(1, w - 1), rng.range(1, h - 1)), rng.roll_dice(1, 100) <= self.clearing_chance))
+            .collect();
+
+        //Which seed each tile is closest to
+        let mut membership = vec![0; (w * h) as usize];
+        for y in 0..h {
+            for x in 0..w {
+                let nearest = seeds.iter()
+                    .enumerate()
+                    .min_by_key(|(_, s)| (s.0.x - x).pow(2) + (s.0.y - y).pow(2))
+                    .map_or(0, |(i, _)| i);
+                membership[map.point2d_to_index(Point::new(x, y))] = nearest;
+            }
+        }
+
+        for y in 1..h-1 {
+            for x in 1..w-1 {
+                let idx = map.point2d_to_index(Point::new(x, y));
+                let cell = membership[idx];
+                let on_seam = [DL_LEFT, DL_RIGHT, DL_UP, DL_DOWN].iter()
+                    .any(|d| membership[map.point2d_to_index(Point::new(x, y) + *d)] != cell);
+
+                map.tiles[idx] = match (on_seam, seeds[cell].1) {
+                    (true, _) => TileClass::ForestFloor,
+                    (false, true) => TileClass::Clearing,
+                    //Just inside a grove the woods haven't quite closed in yet
+                    (false, false) if rng.roll_dice(1, 100) <= 15 => TileClass::Undergrowth,
+                    (false, false) => TileClass::Tree
+                };
+            }
+        }
+
+        if rng.roll_dice(1, 100) <= self.terrain.stream { carve_stream(&mut map, self.terrain.deep_water, rng); }
+
+        map.starting_pos = nearest_open_tile(&map, Point::new(w / 2, h / 2));
+        finish_map(&mut map);
+        return map
+    }
+}
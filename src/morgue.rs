@@ -0,0 +1,98 @@
+use crate::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const MORGUE_DIR: &str = "morgue";
+//How much of the end of the log goes into a morgue file
+pub const MORGUE_LOG_MESSAGES: usize = 20;
+
+impl Game {
+    /// Writes a plain-text record of the run into `dir`, named after the seed and when it ended, and returns where it went.
+    pub fn write_morgue<P: AsRef<Path>>(&self, dir: P) -> std::io::Result<PathBuf> {
+        fs::create_dir_all(&dir)?;
+        let ended = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let path = dir.as_ref().join(format!("morgue-{}-{}.txt", self.world.rng.seed, ended));
+        fs::write(&path, self.morgue_text(MORGUE_LOG_MESSAGES))?;
+        return Ok(path)
+    }
+
+    /// The morgue file's contents: the run's numbers, everyone who ever joined the party, and the end of the log.
+    pub fn morgue_text(&self, log_messages: usize) -> String {
+        let mut text = String::new();
+        let cause = self.stats.killed_by.as_ref().map_or(String::from("still alive"), |k| format!("slain by {}", k));
+
+        text.push_str("Elfball morgue file\n");
+        text.push_str("===================\n\n");
+        text.push_str(&format!("Seed:           {}\n", self.world.rng.seed));
+        text.push_str(&format!("Deepest floor:  {}\n", self.world.max_depth));
+        text.push_str(&format!("Final floor:    {}\n", self.world.depth));
+        text.push_str(&format!("Turns:          {}\n", self.turn));
+        text.push_str(&format!("Fate:           {}\n\n", cause));
+
+        text.push_str(&format!("Forsaken killed: {}\n", self.stats.forsaken_kills));
+        text.push_str(&format!("Beasts killed:   {}\n", self.stats.beast_kills));
+        text.push_str(&format!("Elves rescued:   {}\n\n", self.stats.rescued_elves));
+
+        text.push_str("Party roster\n");
+        text.push_str("------------\n");
+        for entry in self.stats.roster.iter() {
+            let joined = if entry.joined_turn == 0 {
+                String::from("set out at the start of the run")
+            } else {
+                format!("joined on turn {} at depth {}", entry.joined_turn, entry.joined_depth)
+            };
+            let fate = match &entry.death {
+                Some(death) => format!("slain by {} on turn {} at depth {}",
+                    death.killer.as_ref().map_or("unknown causes", |k| k.as_str()), death.turn, death.depth),
+                None => String::from("survived")
+            };
+            text.push_str(&format!("  {} the {}: {}, {}\n", entry.name, entry.class, joined, fate));
+        }
+
+        text.push_str(&format!("\nLast {} log messages\n", log_messages));
+        text.push_str("--------------------\n");
+        let skip = self.logs.len().saturating_sub(log_messages);
+        for entry in self.logs.entries().iter().skip(skip) {
+            text.push_str(&format!("  [turn {:>4}] {}\n", entry.turn, entry.message.parts.join(" ")));
+        }
+
+        return text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn new_game(seed: u64) -> Game {
+        let data = GameData::load().unwrap_or_else(|e| panic!("{}", e));
+        return Game::new(seed, Rc::new(data))
+    }
+
+    #[test]
+    fn morgue_has_the_run_and_its_party() {
+        let game = new_game(42);
+        let text = game.morgue_text(MORGUE_LOG_MESSAGES);
+
+        assert!(text.contains("Seed:           42\n"));
+        assert!(text.contains("Deepest floor:  1\n"));
+        assert!(text.contains("Fate:           still alive\n"));
+        for member in game.world.objects[0].members.iter() {
+            assert!(text.contains(&format!("  {} the {}: set out at the start of the run, survived\n", member.name, member.class)));
+        }
+    }
+
+    #[test]
+    fn morgue_keeps_only_the_end_of_the_log() {
+        let mut game = new_game(42);
+        for i in 0..30 {
+            game.logs.update_logs(LogMessage::new().add_part(format!("entry-{:02}", i), ColorPair::new(WHITE, GREY10)));
+        }
+        let text = game.morgue_text(5);
+
+        for i in 25..30 { assert!(text.contains(&format!("entry-{:02}", i)), "entry {} is missing", i); }
+        for i in 0..25 { assert!(!text.contains(&format!("entry-{:02}", i)), "entry {} should have been left out", i); }
+    }
+}
@@ -1,10 +1,14 @@
 use crate::prelude::*;
 
+/// Anything that exists in the world: the player's band, enemy parties, lost elves. Made up of optional components.
+#[derive(Serialize, Deserialize)]
 pub struct Object {
+    pub id: EntityId,
     pub name: String,
     pub floor: u32,
     pub block_tile: bool,
     pub tag: ActorTag,
+    pub kind: ActorKind,
 
     pub pos: Option<Point>,
     pub render: Option<Render>,
@@ -16,15 +20,18 @@ pub struct Object {
 
     pub members: Vec<PartyMember>,
 
-    pub ai: Option<AIClass>
+    pub ai: Option<AIClass>,
+    pub initiative: Option<Initiative>
 }
 impl Default for Object {
     fn default() -> Self {
         Object {
+            id: EntityId::default(),
             name: String::from("NIL"),
             floor: 0,
             block_tile: true,
             tag: ActorTag::NonActor,
+            kind: ActorKind::NonActor,
             pos: None,
             render: None,
             viewshed: None,
@@ -32,18 +39,73 @@ impl Default for Object {
             inc_attacks: Vec::new(),
             in_combat: false,
             members: Vec::new(),
-            ai: None
+            ai: None,
+            initiative: None
         }
     }
 }
 
+//A handle to an object that stays valid while the objects Vec shifts around. The generation is bumped whenever a
+//slot gets reused, so a handle to something that's been removed can never end up pointing at whatever replaced it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug, Serialize, Deserialize)]
+pub struct EntityId {
+    index: u32,
+    generation: u32
+}
+
+//Hands out entity IDs and keeps track of which ones are still alive. Generations start at 1, so a default ID is never alive.
+#[derive(Serialize, Deserialize)]
+pub struct EntityAllocator {
+    generations: Vec<u32>,
+    free: Vec<u32>
+}
+impl EntityAllocator {
+    pub fn new() -> EntityAllocator { EntityAllocator { generations: Vec::new(), free: Vec::new() } }
+    pub fn allocate(&mut self) -> EntityId {
+        if let Some(index) = self.free.pop() {
+            EntityId { index, generation: self.generations[index as usize] }
+        }
+        else {
+            self.generations.push(1);
+            EntityId { index: self.generations.len() as u32 - 1, generation: 1 }
+        }
+    }
+    pub fn free(&mut self, id: EntityId) {
+        if self.is_alive(id) {
+            self.generations[id.index as usize] += 1;
+            self.free.push(id.index);
+        }
+    }
+    pub fn is_alive(&self, id: EntityId) -> bool {
+        id.generation != 0 && self.generations.get(id.index as usize) == Some(&id.generation)
+    }
+}
+
+//Lookups by entity ID, for anything holding onto the objects list
+pub trait ObjectList {
+    fn index_of(&self, id: EntityId) -> Option<usize>;
+    fn get_by_id(&self, id: EntityId) -> Option<&Object>;
+    fn get_by_id_mut(&mut self, id: EntityId) -> Option<&mut Object>;
+}
+impl ObjectList for Vec<Object> {
+    fn index_of(&self, id: EntityId) -> Option<usize> { self.iter().position(|o| o.id == id) }
+    fn get_by_id(&self, id: EntityId) -> Option<&Object> { self.iter().find(|o| o.id == id) }
+    fn get_by_id_mut(&mut self, id: EntityId) -> Option<&mut Object> { self.iter_mut().find(|o| o.id == id) }
+}
+
 //Component Definitions
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ActorTag {
     NonActor, Player, Enemy, Elf
 }
 
-#[derive(Clone)]
+//What an object's party is made up of, so a band of Forsaken and a beast can be told apart without going by names
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActorKind {
+    NonActor, Elves, Forsaken, Beast
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Render {
     glyph: FontCharType,
     color: ColorPair,
@@ -54,16 +116,85 @@ impl Render {
     pub fn get_render(&self) -> (FontCharType, ColorPair) { return (self.glyph, self.color) }
 }
 
+//How much energy an object spends each time it acts
+pub const ENERGY_PER_ACTION: u32 = 100;
+
+//How often an object gets to act. Energy builds up by `speed` every tick, so a speed of 200 acts twice
+//for every time the player does, and a speed of 50 acts every other turn. Wading through slow terrain
+//costs more than one action's worth, which can leave the energy below zero for a while.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Initiative {
+    pub speed: u32,
+    pub energy: i32,
+    //What the action being taken costs, in multiples of a normal one
+    pub action_cost: f32
+}
+impl Initiative {
+    pub fn new(speed: u32) -> Initiative { Initiative { speed, energy: 0, action_cost: 1.0 } }
+    //Starts out ready to act straight away
+    pub fn ready(speed: u32) -> Initiative { Initiative { speed, energy: ENERGY_PER_ACTION as i32, action_cost: 1.0 } }
+    pub fn is_due(&self) -> bool { self.energy >= ENERGY_PER_ACTION as i32 }
+    pub fn gain_energy(&mut self) { self.energy += self.speed as i32 }
+    pub fn spend_energy(&mut self) {
+        self.energy -= (ENERGY_PER_ACTION as f32 * self.action_cost).round() as i32;
+        self.action_cost = 1.0;
+    }
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Viewshed {
     pub range: i32,
     pub visible: Vec<Point>,
     pub refresh: bool
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct PlayerMemory {
     pub seen: bool,
     pub last_pos: Option<Point>
 }
 impl Default for PlayerMemory {
     fn default() -> Self { PlayerMemory { seen: false, last_pos: None } }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_id_does_not_find_whatever_took_its_slot() {
+        let mut entities = EntityAllocator::new();
+        let mut objects: Vec<Object> = Vec::new();
+        for name in ["player", "band", "beast"].iter() {
+            objects.push(Object { id: entities.allocate(), name: String::from(*name), ..Default::default() });
+        }
+
+        let band = objects[1].id;
+        let idx = objects.index_of(band).unwrap();
+        objects.remove(idx);
+        entities.free(band);
+
+        //The freed slot gets handed straight back out to the next object
+        let elf = entities.allocate();
+        objects.push(Object { id: elf, name: String::from("elf"), ..Default::default() });
+        assert_eq!(elf.index, band.index);
+        assert_ne!(elf, band);
+
+        assert!(!entities.is_alive(band));
+        assert!(entities.is_alive(elf));
+        assert!(objects.get_by_id(band).is_none());
+        assert_eq!(objects.get_by_id(elf).unwrap().name, "elf");
+    }
+
+    #[test]
+    fn freeing_twice_does_not_hand_the_slot_out_twice() {
+        let mut entities = EntityAllocator::new();
+        let first = entities.allocate();
+        entities.free(first);
+        entities.free(first);
+
+        let a = entities.allocate();
+        let b = entities.allocate();
+        assert_ne!(a, b);
+        assert!(entities.is_alive(a) && entities.is_alive(b));
+    }
+}
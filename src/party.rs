@@ -1,6 +1,8 @@
 use crate::prelude::*;
+use std::collections::HashSet;
 
-#[derive(Clone)]
+/// A single elf (or beast) inside an object's party, with its own health, attack, threat and abilities.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PartyMember {
     pub name: String,
     pub class: String,
@@ -12,7 +14,64 @@ pub struct PartyMember {
     pub modifiers: Vec<Modifier>
 }
 
-#[derive(Clone)]
+//One class a party member can have, as written in the classes file
+#[derive(Clone, Deserialize)]
+pub struct ClassDef {
+    pub name: String,
+    pub glyph: FontCharType,
+    pub color: (u8, u8, u8),
+    pub hp: i32,
+    pub damage: (i32, i32),
+    pub threat_gain: u32,
+    pub threat_start: u32,
+    #[serde(default)]
+    pub modifiers: Vec<ModifierEffect>,
+    #[serde(default)]
+    pub abilities: Vec<String>
+}
+impl ClassDef {
+    //Rolls up a fresh elf of this class
+    pub fn make_member(&self, data: &GameData, rng: &mut RandomNumberGenerator) -> PartyMember {
+        let (r, g, b) = self.color;
+        PartyMember {
+            name: make_random_elf_name(rng),
+            class: self.name.clone(),
+            icon: Render::new(self.glyph, ColorPair::new(RGB::from_u8(r, g, b), BLACK), 255),
+            abilities: self.abilities.iter().filter_map(|a| data.ability(a)).map(AbilityClass::new).collect(),
+            health: Health::new(self.hp),
+            attack: Attack::new(self.damage.0, self.damage.1),
+            threat: Threat::new(self.threat_gain, self.threat_start),
+            modifiers: self.modifiers.iter().map(|m| Modifier::new(*m, 0, true)).collect(),
+        }
+    }
+}
+
+//Everything the classes file can get wrong on its own. The abilities each class names are checked once both files are loaded.
+pub fn validate_classes(classes: &Vec<ClassDef>) -> Result<(), String> {
+    if classes.is_empty() {
+        return Err(String::from("at least one class has to be defined"))
+    }
+
+    let mut seen = HashSet::new();
+    for class in classes.iter() {
+        if class.name.trim().is_empty() {
+            return Err(String::from("a class is missing its name"))
+        }
+        if !seen.insert(class.name.as_str()) {
+            return Err(format!("the class \"{}\" is defined more than once", class.name))
+        }
+        if class.hp <= 0 {
+            return Err(format!("the class \"{}\" needs more than 0 hp", class.name))
+        }
+        if class.damage.0 <= 0 || class.damage.1 <= 0 {
+            return Err(format!("the class \"{}\" has damage {}d{}, both numbers must be at least 1", class.name, class.damage.0, class.damage.1))
+        }
+    }
+
+    return Ok(())
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Modifier {
     pub effect: ModifierEffect,
     ttl: u32,
@@ -32,12 +91,12 @@ impl Modifier {
         }
     }
 }
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ModifierEffect {
     PlusAttack(i32), PlusThreat(i32), Block(i32)
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Health {
     max: i32,
     current: i32,
@@ -67,7 +126,7 @@ impl Health {
     pub fn reset_block(&mut self) { self.block = 0 }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Attack {
     damage: (i32,i32),
     modifier: i32,
@@ -84,7 +143,7 @@ impl Attack {
     pub fn enable_attack(&mut self) { self.able_to_attack = true }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Threat {
     current: u32,
     starting: u32,
@@ -108,19 +167,19 @@ impl Threat {
     pub fn reset_modifier(&mut self) { self.modifier = 0 }
 }
 
-#[derive(Clone,Copy)]
+#[derive(Clone,Serialize,Deserialize)]
 pub struct AbilityClass {
-    pub ability: Ability,
+    pub ability: String,
     pub on_cooldown: bool,
     pub min_cooldown: i32,
     pub cooldown_timer: i32
 }
 impl AbilityClass {
-    pub fn new(ability: Ability) -> AbilityClass {
+    pub fn new(def: &AbilityDef) -> AbilityClass {
         AbilityClass {
-            ability,
+            ability: def.name.clone(),
             on_cooldown: false,
-            min_cooldown: get_ability_cooldown(ability),
+            min_cooldown: def.cooldown,
             cooldown_timer: 0
         }
     }
@@ -138,9 +197,7 @@ impl AbilityClass {
 }
 
 
-pub fn make_random_elf_name() -> String {
-    let mut rng = RandomNumberGenerator::new();
-
+pub fn make_random_elf_name(rng: &mut RandomNumberGenerator) -> String {
     let rand1 = rng.range(1, 101);
     let rand2 = rng.range(1, 101);
 
@@ -1,22 +1,31 @@
 use crate::prelude::*;
 
+//How much of the message history the log viewer shows at once
+pub const LOG_VIEWER_WIDTH: usize = (CONSOLE_W * 2 - 6) as usize;
+pub const LOG_VIEWER_ROWS: usize = (CONSOLE_H - 5) as usize;
+
 pub fn render_loop(gs: &State, con: &mut BTerm) {
     con.cls();
     render_draw_buffer(con).expect("Failed to render");
     match gs.status {
         ContextState::GameOver => {
-            batch_game_over_message(gs);
+            batch_game_over_message(&gs.game);
+            batch_high_scores(&gs.high_scores, gs.last_rank);
+        }
+        ContextState::LogViewer => {
+            batch_log_viewer(&gs.game.logs, gs.log_scroll, &gs.log_hidden);
         }
         ContextState::InGame => {
-            batch_map_draws(&gs.world.map, &gs.world.camera);
-            batch_entity_draws(&gs.world.objects, &gs.world.map, &gs.world.camera, gs.world.depth, gs.player_targets.get_current_target());
-            batch_ui_draws(&gs.world.objects, &gs.logs, &gs.stored_abilities, gs.player_targets.get_current_target());
+            let game = &gs.game;
+            batch_map_draws(&game.world.map, &game.world.camera, &game.world.data.biome(game.world.depth).palette);
+            batch_entity_draws(&game.world.objects, &game.world.map, &game.world.camera, game.world.depth, game.player_targets.get_current_target());
+            batch_ui_draws(&game.world.objects, &game.logs, &game.stored_abilities, game.player_targets.get_current_target());
         }
     }
     render_draw_buffer(con).expect("Failed to render");
 }
 
-fn batch_game_over_message(gs: &State) {
+fn batch_game_over_message(gs: &Game) {
     let mut bg_batch = DrawBatch::new();
     let mut txt_batch = DrawBatch::new();
     bg_batch.target(MAP_CON);
@@ -28,10 +37,12 @@ fn batch_game_over_message(gs: &State) {
     txt_batch.draw_double_box(message_region, ColorPair::new(BLACK, RED));
     txt_batch.print_color_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1), "GAME OVER", ColorPair::new(BLACK, RED));
     txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 2), "Your party has been wiped out.");
-    txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 5), format!("You ventured {} levels deep into the forest.", gs.world.depth));
-    txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 6), format!("You killed {} Forsaken elves.", gs.forsaken_kills));
-    txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 7), format!("You killed {} forgotten beasts.", gs.beast_kills));
-    txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 8), format!("You rescued {} fellow elves.", gs.rescued_elves));
+    txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 5), format!("You ventured {} levels deep into the forest.", gs.world.max_depth));
+    txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 6), format!("You killed {} Forsaken elves.", gs.stats.forsaken_kills));
+    txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 7), format!("You killed {} forgotten beasts.", gs.stats.beast_kills));
+    txt_batch.print_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 8), format!("You rescued {} fellow elves.", gs.stats.rescued_elves));
+    txt_batch.print_color_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 10), format!("Final score: {}", run_score(gs)), ColorPair::new(GOLD, BLACK));
+    txt_batch.print_color_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y1 + 11), format!("Run seed: {}", gs.world.rng.seed), ColorPair::new(GOLD, BLACK));
     txt_batch.print_color_centered_at(Point::new(message_region.x1 + message_region.width()/2, message_region.y2 - 2), "Press ENTER to start a new game.", ColorPair::new(LIME_GREEN, BLACK));
 
     bg_batch.submit(0).expect("Failed to batch game over draw");
@@ -39,7 +50,7 @@ fn batch_game_over_message(gs: &State) {
 }
 
 //Adds all map tiles to the rendering batch.
-fn batch_map_draws(map: &Map, camera: &Camera) {
+fn batch_map_draws(map: &Map, camera: &Camera, palette: &Palette) {
     let mut batch = DrawBatch::new();
     batch.target(MAP_CON);
     let offset = Point::new(camera.min_x, camera.min_y);
@@ -52,8 +63,8 @@ fn batch_map_draws(map: &Map, camera: &Camera) {
                 let idx = map.point2d_to_index(pos);
 
                 let (glyph, colors) = match (map.visible[idx], map.revealed[idx]) {
-                    (true, _) => {get_tile_render(&map.tiles[idx])},
-                    (false, true) => {(get_tile_render(&map.tiles[idx]).0, ColorPair::new(GREY10,BLACK))},
+                    (true, _) => {map.tiles[idx].render(palette)},
+                    (false, true) => {(map.tiles[idx].render(palette).0, ColorPair::new(GREY10,BLACK))},
                     (false, false) => {(0,ColorPair::new(BLACK,BLACK))},
                 };
 
@@ -67,8 +78,74 @@ fn batch_map_draws(map: &Map, camera: &Camera) {
     batch.submit(0).expect("Failed to batch map draw");
 }
 
+//Fills the screen with the message history, ending `scroll` lines up from the latest one
+fn batch_log_viewer(logs: &LogBuffer, scroll: usize, hidden: &[LogCategory]) {
+    let mut bg_batch = DrawBatch::new();
+    let mut txt_batch = DrawBatch::new();
+    bg_batch.target(MAP_CON);
+    txt_batch.target(TEXT_CON);
+
+    bg_batch.fill_region(Rect::with_exact(0,0,CONSOLE_W * 2, CONSOLE_H), ColorPair::new(BLACK, BLACK), 0);
+    let view_region = Rect::with_exact(0, 0, CONSOLE_W * 2 - 1, CONSOLE_H - 1);
+    txt_batch.draw_double_box(view_region, ColorPair::new(GREY50, BLACK));
+    txt_batch.print_color_centered_at(Point::new(view_region.width()/2, view_region.y1), "MESSAGE HISTORY", ColorPair::new(BLACK, GOLD));
+
+    let lines = logs.history_lines(LOG_VIEWER_WIDTH, hidden);
+    let end = lines.len() - scroll.min(lines.len());
+    let start = end.saturating_sub(LOG_VIEWER_ROWS);
+    for (row, line) in lines[start..end].iter().enumerate() {
+        let mut x = view_region.x1 + 3;
+        for (i, part) in line.parts.iter().enumerate() {
+            txt_batch.print_color(Point::new(x, view_region.y1 + 2 + row as i32), part, line.colors[i]);
+            x += part.chars().count() as i32;
+        }
+    }
+
+    //Which categories are showing, greyed out when they're hidden
+    let mut x = view_region.x1 + 3;
+    for (i, category) in LogCategory::ALL.iter().enumerate() {
+        let color = if hidden.contains(category) { ColorPair::new(GREY30, BLACK) } else { ColorPair::new(GOLD, BLACK) };
+        let label = format!("({}) {}  ", i + 1, category.name());
+        txt_batch.print_color(Point::new(x, view_region.y2 - 2), &label, color);
+        x += label.chars().count() as i32;
+    }
+
+    let position = if scroll == 0 { String::from("latest") } else { format!("{} lines back", scroll) };
+    txt_batch.print_color(Point::new(view_region.x1 + 3, view_region.y2 - 1),
+        format!("PageUp/PageDown scroll, Home/End jump to the start/latest, 1-5 show/hide a category, E exports, M or Esc closes. ({})", position),
+        ColorPair::new(LIME_GREEN, BLACK));
+
+    bg_batch.submit(0).expect("Failed to batch log viewer draw");
+    txt_batch.submit(1).expect("Failed to batch log viewer draw");
+}
+
+//Lists the high score table under the game over message, picking out the run that just ended
+fn batch_high_scores(scores: &HighScores, last_rank: Option<usize>) {
+    let mut txt_batch = DrawBatch::new();
+    txt_batch.target(TEXT_CON);
+
+    let table_region = Rect::with_exact(CONSOLE_W - 40, 42, CONSOLE_W + 40, 56);
+    txt_batch.draw_double_box(table_region, ColorPair::new(GREY50, BLACK));
+    txt_batch.print_color_centered_at(Point::new(table_region.x1 + table_region.width()/2, table_region.y1), "HIGH SCORES", ColorPair::new(BLACK, GOLD));
+
+    if scores.entries.is_empty() {
+        txt_batch.print_centered_at(Point::new(table_region.x1 + table_region.width()/2, table_region.y1 + 2), "No runs recorded yet.");
+    }
+    let max_width = (table_region.width() - 3) as usize;
+    for (i, entry) in scores.entries.iter().enumerate() {
+        let mut row = format!("{:>2}. {:>6}  {}  depth {:>2}  seed {:<20}  {}",
+            i + 1, entry.score, entry.date, entry.depth, entry.seed, entry.party.join(", "));
+        if row.chars().count() > max_width { row = row.chars().take(max_width).collect(); }
+
+        let color = if Some(i) == last_rank { ColorPair::new(BLACK, GOLD) } else { ColorPair::new(WHITE, BLACK) };
+        txt_batch.print_color(Point::new(table_region.x1 + 2, table_region.y1 + 2 + i as i32), row, color);
+    }
+
+    txt_batch.submit(2).expect("Failed to batch high score draw");
+}
+
 //Adds all visible entity renderables to the rendering batch.
-fn batch_entity_draws(objects: &Vec<Object>, map: &Map, camera: &Camera, floor: u32, player_target: Option<usize>) {
+fn batch_entity_draws(objects: &Vec<Object>, map: &Map, camera: &Camera, floor: u32, player_target: Option<EntityId>) {
     let mut batch = DrawBatch::new();
     batch.target(MAP_CON);
     let offset = Point::new(camera.min_x, camera.min_y);
@@ -76,7 +153,7 @@ fn batch_entity_draws(objects: &Vec<Object>, map: &Map, camera: &Camera, floor:
     let mut target_pos: Option<Point> = None;
     //Grab all objects that are drawable and have a position (force the player in at the end)
     let mut render_list: Vec<(&Object, bool)> = Vec::new();
-    for (i, object) in objects.iter().enumerate() {
+    for object in objects.iter() {
         if object.pos.is_some() && object.render.is_some() {
             let pos = object.pos.as_ref().unwrap();
             let idx = map.point2d_to_index(*pos);
@@ -88,7 +165,7 @@ fn batch_entity_draws(objects: &Vec<Object>, map: &Map, camera: &Camera, floor:
                 }
             }
             if let Some(tgt) = player_target {
-                if i == tgt { target_pos = object.pos }
+                if object.id == tgt { target_pos = object.pos }
             }
         }
     }
@@ -114,7 +191,7 @@ fn batch_entity_draws(objects: &Vec<Object>, map: &Map, camera: &Camera, floor:
     batch.submit(5000).expect("Failed to batch entity draw");
 }
 
-fn batch_ui_draws(objects: &Vec<Object>, logs: &LogBuffer, abilities: &Vec<StoredAbility>, target: Option<usize>) {
+fn batch_ui_draws(objects: &Vec<Object>, logs: &LogBuffer, abilities: &Vec<StoredAbility>, target: Option<EntityId>) {
     let mut bg_batch = DrawBatch::new();
     let mut txt_batch = DrawBatch::new();
     bg_batch.target(MAP_CON);
@@ -163,8 +240,8 @@ fn batch_ui_draws(objects: &Vec<Object>, logs: &LogBuffer, abilities: &Vec<Store
         let player_party = &objects[0].members;
         let threat_table = make_threat_table(&player_party);
 
-        for (i, member) in player_party.iter().enumerate() {
-            let sbox = party_sub_boxes[i];
+        //Bigger parties than the panel has room for only show their first few members
+        for (i, (member, sbox)) in player_party.iter().zip(party_sub_boxes.iter()).enumerate() {
             txt_batch.print_color(Point::new(sbox.x1, sbox.y1), ".............", ColorPair::new(GREY15, BLACK));
             txt_batch.print_color(Point::new(sbox.x1, sbox.y1), format!("{}", member.name), ColorPair::new(member.icon.get_render().1.fg, BLACK));
             txt_batch.print_color_right(Point::new(sbox.x2, sbox.y1), format!("{}", to_char(member.icon.get_render().0 as u8)), ColorPair::new(member.icon.get_render().1.fg, BLACK));
@@ -181,12 +258,11 @@ fn batch_ui_draws(objects: &Vec<Object>, logs: &LogBuffer, abilities: &Vec<Store
         }
     }
     {
-        if let Some(tgt) = target {
-            let target_party = &objects[tgt].members;
+        if let Some(tgt) = target.and_then(|id| objects.get_by_id(id)) {
+            let target_party = &tgt.members;
             let threat_table = make_threat_table(&target_party);
 
-            for (i, member) in target_party.iter().enumerate() {
-                let sbox = combat_sub_boxes[i];
+            for (i, (member, sbox)) in target_party.iter().zip(combat_sub_boxes.iter()).enumerate() {
                 txt_batch.print_color(Point::new(sbox.x1, sbox.y1), ".............", ColorPair::new(GREY15, BLACK));
                 txt_batch.print_color(Point::new(sbox.x1, sbox.y1), format!("{}", member.name), ColorPair::new(member.icon.get_render().1.fg, BLACK));
                 txt_batch.print_color_right(Point::new(sbox.x2, sbox.y1), format!("{}", to_char(member.icon.get_render().0 as u8)), ColorPair::new(member.icon.get_render().1.fg, BLACK));
@@ -246,14 +322,6 @@ fn batch_ui_draws(objects: &Vec<Object>, logs: &LogBuffer, abilities: &Vec<Store
     txt_batch.submit(11000).expect("Failed to batch UI draw");
 }
 
-fn get_tile_render(tile: &TileClass) -> (FontCharType, ColorPair) {
-    match tile {
-        TileClass::ForestFloor => (46, ColorPair::new(BROWN1,BLACK)),
-        TileClass::Tree => (5, ColorPair::new(GREEN,BLACK)),
-        TileClass::ForestPortal => (21, ColorPair::new(CYAN,BLACK)),
-    }
-}
-
 fn get_divider_boxes(source_rect: &Rect) -> Vec<Rect> {
     let mut boxes = Vec::new();
     let width_increment = source_rect.width()/4;
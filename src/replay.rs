@@ -0,0 +1,145 @@
+use crate::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+pub const REPLAY_VERSION: u32 = 1;
+pub const REPLAY_PATH: &str = "replay.json";
+
+/// Everything needed to reproduce a run: the seed, then every action in order along with a hash of the game state it led to
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub turns: Vec<RecordedTurn>
+}
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct RecordedTurn {
+    pub action: Actions,
+    pub hash: u64
+}
+
+//Where a replay stopped matching the recording
+pub struct Desync {
+    pub turn: usize,
+    pub expected: u64,
+    pub found: u64
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Replay { Replay { version: REPLAY_VERSION, seed, turns: Vec::new() } }
+    pub fn record(&mut self, action: Actions, hash: u64) { self.turns.push(RecordedTurn { action, hash }) }
+
+    pub fn write_to_disk<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        return Ok(())
+    }
+    pub fn read_from_disk<P: AsRef<Path>>(path: P) -> Result<Replay, SaveError> {
+        let replay: Replay = serde_json::from_str(&fs::read_to_string(path)?)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(SaveError::WrongVersion { found: replay.version, expected: REPLAY_VERSION })
+        }
+        return Ok(replay)
+    }
+
+    //Plays the whole recording back without a window, stopping at the first turn that doesn't match
+    pub fn verify(&self, data: Rc<GameData>) -> Result<Game, Desync> {
+        let mut game = Game::new(self.seed, data);
+        for (i, turn) in self.turns.iter().enumerate() {
+            game.step(turn.action);
+            let found = game.state_hash();
+            if found != turn.hash {
+                return Err(Desync { turn: i, expected: turn.hash, found })
+            }
+        }
+        return Ok(game)
+    }
+}
+
+//Steps through a recording one action at a time, for watching a replay in the window
+pub struct Playback {
+    pub replay: Replay,
+    pub next_turn: usize
+}
+impl Playback {
+    pub fn new(replay: Replay) -> Playback { Playback { replay, next_turn: 0 } }
+    pub fn is_finished(&self) -> bool { self.next_turn >= self.replay.turns.len() }
+
+    //Feeds the next recorded action into the game, and hands back a desync if the result doesn't match
+    pub fn advance(&mut self, game: &mut Game) -> Option<Desync> {
+        if self.is_finished() { return None }
+
+        let turn = self.replay.turns[self.next_turn];
+        game.step(turn.action);
+        self.next_turn += 1;
+
+        let found = game.state_hash();
+        return if found != turn.hash {
+            Some(Desync { turn: self.next_turn - 1, expected: turn.hash, found })
+        } else {
+            None
+        }
+    }
+}
+
+//FNV-1a, written out by hand and fed bytes in a fixed order, so a state hashes the same on every machine and
+//every compiler version. The standard library's hasher makes no such promise.
+struct StateHasher(u64);
+impl StateHasher {
+    fn new() -> StateHasher { StateHasher(0xcbf29ce484222325) }
+    fn bytes(&mut self, bytes: &[u8]) {
+        for b in bytes.iter() {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+    fn u32(&mut self, n: u32) { self.bytes(&n.to_le_bytes()) }
+    fn i32(&mut self, n: i32) { self.bytes(&n.to_le_bytes()) }
+    //Length first, so two strings run together can't hash the same as a different pair
+    fn str(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.bytes(s.as_bytes());
+    }
+    fn point(&mut self, p: Option<Point>) {
+        match p {
+            Some(p) => { self.bytes(&[1]); self.i32(p.x); self.i32(p.y); }
+            None => self.bytes(&[0])
+        }
+    }
+}
+
+impl Game {
+    //A fingerprint of the parts of the game that gameplay can change. The log is left out on purpose.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+
+        hasher.u32(self.world.depth);
+        hasher.u32(self.world.max_depth);
+        hasher.point(Some(self.world.map.exit_pos));
+        hasher.u32(self.turn);
+        hasher.u32(self.stats.forsaken_kills);
+        hasher.u32(self.stats.beast_kills);
+        hasher.u32(self.stats.rescued_elves);
+        //Abilities like clearing trees change the ground itself
+        for tile in self.world.map.tiles.iter() {
+            hasher.bytes(&[*tile as u8]);
+        }
+
+        for obj in self.world.objects.iter() {
+            hasher.str(&obj.name);
+            hasher.u32(obj.floor);
+            hasher.point(obj.pos);
+            if let Some(init) = &obj.initiative { hasher.i32(init.energy); }
+            for member in obj.members.iter() {
+                hasher.str(&member.name);
+                hasher.i32(member.health.get_life());
+                hasher.u32(member.threat.get_threat());
+                for ability in member.abilities.iter() {
+                    hasher.i32(ability.cooldown_timer);
+                }
+            }
+        }
+
+        return hasher.0
+    }
+}
@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+/// Every random roll in a run comes from one of these streams. They're all derived from the single run seed,
+/// so each part of the game can roll as much as it likes without shifting the results of the others.
+#[derive(Serialize, Deserialize)]
+pub struct RunRng {
+    pub seed: u64,
+    pub map: RandomNumberGenerator,
+    pub spawns: RandomNumberGenerator,
+    pub names: RandomNumberGenerator,
+    pub combat: RandomNumberGenerator,
+    pub ai: RandomNumberGenerator
+}
+impl RunRng {
+    pub fn new(seed: u64) -> RunRng {
+        RunRng {
+            seed,
+            map: RandomNumberGenerator::seeded(derive_seed(seed, 1)),
+            spawns: RandomNumberGenerator::seeded(derive_seed(seed, 2)),
+            names: RandomNumberGenerator::seeded(derive_seed(seed, 3)),
+            combat: RandomNumberGenerator::seeded(derive_seed(seed, 4)),
+            ai: RandomNumberGenerator::seeded(derive_seed(seed, 5))
+        }
+    }
+    //Picks a fresh seed for when the player didn't ask for one
+    pub fn random_seed() -> u64 { RandomNumberGenerator::new().next_u64() }
+}
+
+//Mixes the run seed with a stream number (splitmix64), so neighbouring seeds don't give neighbouring streams
+fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    return z ^ (z >> 31)
+}
@@ -0,0 +1,69 @@
+use crate::prelude::*;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+//Bump this whenever anything saved inside Game changes shape, so older saves get turned away instead of misread
+pub const SAVE_VERSION: u32 = 1;
+pub const SAVE_PATH: &str = "savegame.json";
+
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    version: u32,
+    game: &'a Game
+}
+
+//Read on its own first, so the version can be checked before trying to make sense of the rest
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32
+}
+
+#[derive(Deserialize)]
+struct LoadedSave {
+    game: Game
+}
+
+pub enum SaveError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    WrongVersion { found: u32, expected: u32 }
+}
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "could not access the save file ({})", e),
+            SaveError::Format(e) => write!(f, "the save file is damaged ({})", e),
+            SaveError::WrongVersion { found, expected } =>
+                write!(f, "the save file is from version {} of the format, but this build reads version {}", found, expected),
+        }
+    }
+}
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self { SaveError::Io(e) }
+}
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self { SaveError::Format(e) }
+}
+
+impl Game {
+    pub fn save_to_disk<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        let contents = serde_json::to_string(&SaveFile { version: SAVE_VERSION, game: self })?;
+        fs::write(path, contents)?;
+        return Ok(())
+    }
+
+    pub fn load_from_disk<P: AsRef<Path>>(path: P, data: Rc<GameData>) -> Result<Game, SaveError> {
+        let contents = fs::read_to_string(path)?;
+
+        let header: SaveHeader = serde_json::from_str(&contents)?;
+        if header.version != SAVE_VERSION {
+            return Err(SaveError::WrongVersion { found: header.version, expected: SAVE_VERSION })
+        }
+
+        let mut loaded: LoadedSave = serde_json::from_str(&contents)?;
+        loaded.game.world.data = data;
+        return Ok(loaded.game)
+    }
+}
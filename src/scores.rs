@@ -0,0 +1,153 @@
+use crate::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SCORES_PATH: &str = "highscores.json";
+//How many runs the table keeps
+pub const MAX_HIGH_SCORES: usize = 10;
+
+//Points for each thing a run can achieve. Getting deeper is worth the most, beasts are worth more than the Forsaken.
+const SCORE_PER_DEPTH: u32 = 100;
+const SCORE_PER_FORSAKEN: u32 = 10;
+const SCORE_PER_BEAST: u32 = 50;
+const SCORE_PER_RESCUE: u32 = 25;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    pub score: u32,
+    //When the run ended, as YYYY-MM-DD
+    pub date: String,
+    pub seed: u64,
+    pub depth: u32,
+    //Classes of the party as it went into its last fight
+    pub party: Vec<String>
+}
+impl ScoreEntry {
+    pub fn from_game(game: &Game) -> ScoreEntry {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        ScoreEntry {
+            score: run_score(game),
+            date: format_date(now),
+            seed: game.world.rng.seed,
+            depth: game.world.max_depth,
+            party: game.stats.final_party.clone()
+        }
+    }
+}
+
+/// Ranks a run by how deep it got, what it killed and who it rescued.
+pub fn run_score(game: &Game) -> u32 {
+    return game.world.max_depth * SCORE_PER_DEPTH
+        + game.stats.forsaken_kills * SCORE_PER_FORSAKEN
+        + game.stats.beast_kills * SCORE_PER_BEAST
+        + game.stats.rescued_elves * SCORE_PER_RESCUE
+}
+
+/// The best runs played on this machine, highest score first.
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<ScoreEntry>
+}
+impl HighScores {
+    //A missing file just means nothing has been recorded yet
+    pub fn load_from_disk<P: AsRef<Path>>(path: P) -> Result<HighScores, SaveError> {
+        if !path.as_ref().exists() { return Ok(HighScores::default()) }
+        let contents = fs::read_to_string(path)?;
+        return Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save_to_disk<P: AsRef<Path>>(&self, path: P) -> Result<(), SaveError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        return Ok(())
+    }
+
+    /// Adds a run to the table, returning where it ranked if it was good enough to stay on it.
+    /// Ties go to whoever got there first.
+    pub fn insert(&mut self, entry: ScoreEntry) -> Option<usize> {
+        let rank = self.entries.iter().position(|e| e.score < entry.score).unwrap_or(self.entries.len());
+        if rank >= MAX_HIGH_SCORES { return None }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        return Some(rank)
+    }
+}
+
+//Turns seconds since 1970 into a calendar date, without pulling in a date library for it
+fn format_date(secs: u64) -> String {
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    return format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: u32, seed: u64) -> ScoreEntry {
+        ScoreEntry { score, date: String::from("2026-01-01"), seed, depth: 1, party: Vec::new() }
+    }
+    fn seeds(table: &HighScores) -> Vec<u64> { table.entries.iter().map(|e| e.seed).collect() }
+
+    #[test]
+    fn runs_are_ranked_highest_first() {
+        let mut table = HighScores::default();
+        assert_eq!(table.insert(entry(200, 1)), Some(0));
+        assert_eq!(table.insert(entry(500, 2)), Some(0));
+        assert_eq!(table.insert(entry(300, 3)), Some(1));
+        assert_eq!(seeds(&table), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn ties_go_to_whoever_got_there_first() {
+        let mut table = HighScores::default();
+        table.insert(entry(300, 1));
+        assert_eq!(table.insert(entry(300, 2)), Some(1));
+        assert_eq!(seeds(&table), vec![1, 2]);
+    }
+
+    #[test]
+    fn table_is_cut_off_at_the_maximum() {
+        let mut table = HighScores::default();
+        for i in 0..MAX_HIGH_SCORES as u64 {
+            table.insert(entry(100 * (i as u32 + 1), i));
+        }
+        assert_eq!(table.entries.len(), MAX_HIGH_SCORES);
+
+        //Too low to make it onto a full table
+        assert_eq!(table.insert(entry(50, 100)), None);
+        //Tying the lowest score on a full table isn't enough either
+        assert_eq!(table.insert(entry(100, 101)), None);
+        assert_eq!(table.entries.len(), MAX_HIGH_SCORES);
+
+        //A new best pushes the lowest one off the bottom
+        assert_eq!(table.insert(entry(10000, 102)), Some(0));
+        assert_eq!(table.entries.len(), MAX_HIGH_SCORES);
+        assert_eq!(table.entries[0].seed, 102);
+        assert!(!table.entries.iter().any(|e| e.score == 100));
+    }
+
+    #[test]
+    fn dates_come_out_right() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(86399), "1970-01-01");
+        assert_eq!(format_date(86400), "1970-01-02");
+        assert_eq!(format_date(946684799), "1999-12-31");
+        assert_eq!(format_date(946684800), "2000-01-01");
+        //Leap days, including 2000 which is divisible by 400
+        assert_eq!(format_date(951782400), "2000-02-29");
+        assert_eq!(format_date(951868800), "2000-03-01");
+        assert_eq!(format_date(1709164800), "2024-02-29");
+        assert_eq!(format_date(1709251199), "2024-02-29");
+        assert_eq!(format_date(1709251200), "2024-03-01");
+    }
+}
@@ -1,29 +1,135 @@
 use crate::prelude::*;
 use std::cmp::min;
 
-pub fn spawn_player(pos: Point) -> Object {
+//The ability the Forsaken casters are born with
+pub const CASTER_ABILITY: &str = "Psy-Bolt";
+//The Forsaken and the beasts aren't in the classes file, so their colours live here. Elves take theirs from their class.
+pub const WARRIOR_COLOR: (u8, u8, u8) = PURPLE;
+pub const CASTER_COLOR: (u8, u8, u8) = BLUE_VIOLET;
+pub const BEAST_COLOR: (u8, u8, u8) = RED;
+//The player's party as a whole, as opposed to any one elf in it
+pub const PLAYER_COLOR: (u8, u8, u8) = GOLD1;
+pub const CASTER_CLASS: &str = "Psychomancer";
+//How quickly the player's party acts. Everything else's speed is measured against this.
+pub const PLAYER_SPEED: u32 = ENERGY_PER_ACTION;
+
+//What a stretch of depths gets populated with, as written in the spawn tables file
+#[derive(Clone, Deserialize)]
+pub struct SpawnTable {
+    //The first and last depth this table covers. Leaving out the last one covers every depth from there down.
+    pub from: u32,
+    #[serde(default)]
+    pub to: Option<u32>,
+    //Only used on floors of this biome, where it takes over from the general table for the same depth
+    #[serde(default)]
+    pub biome: Option<String>,
+    pub bands: u32,
+    //Smallest and largest number of Forsaken in a band
+    pub band_size: (u32, u32),
+    pub members: Vec<(ForsakenKind, u32)>,
+    pub beasts: u32,
+    pub elves: u32,
+    //How quickly bands and beasts act, where the player's speed is 100. Leaving either out keeps pace with the player.
+    #[serde(default = "default_speed")]
+    pub band_speed: u32,
+    #[serde(default = "default_speed")]
+    pub beast_speed: u32
+}
+fn default_speed() -> u32 { PLAYER_SPEED }
+impl SpawnTable {
+    pub fn covers(&self, depth: u32) -> bool { depth >= self.from && self.to.map_or(true, |to| depth <= to) }
+
+    //Picks a kind of Forsaken, each one as likely as its weight
+    fn roll_member(&self, rng: &mut RandomNumberGenerator) -> ForsakenKind {
+        let total: u32 = self.members.iter().map(|m| m.1).sum();
+        let mut roll = rng.range(0, total);
+        for (kind, weight) in self.members.iter() {
+            if roll < *weight { return *kind }
+            roll -= weight;
+        }
+        return self.members[self.members.len() - 1].0
+    }
+}
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+pub enum ForsakenKind {
+    Warrior, Caster
+}
+
+//The general tables have to cover every depth exactly once, from the first floor all the way down. Biome tables
+//only stand in for them, so they can cover whichever depths they like; which biomes they name is checked against
+//the biomes file once both are loaded.
+pub fn validate_spawn_tables(tables: &Vec<SpawnTable>) -> Result<(), String> {
+    let general: Vec<usize> = (0..tables.len()).filter(|i| tables[*i].biome.is_none()).collect();
+    if general.is_empty() {
+        return Err(String::from("at least one spawn table without a biome has to be defined"))
+    }
+
+    let mut next_depth = 1;
+    for (i, table) in tables.iter().enumerate() {
+        if let Some(to) = table.to {
+            if to < table.from {
+                return Err(format!("spawn table {} ends at depth {}, before it starts", i + 1, to))
+            }
+        }
+        if table.biome.is_none() {
+            if table.from != next_depth {
+                return Err(format!("spawn table {} starts at depth {}, but the one before it leaves off at depth {}", i + 1, table.from, next_depth))
+            }
+            match table.to {
+                Some(to) => next_depth = to + 1,
+                None if i != general[general.len() - 1] =>
+                    return Err(format!("spawn table {} has no last depth, so it has to be the final table", i + 1)),
+                None => {}
+            }
+        }
+        if table.band_size.0 == 0 || table.band_size.0 > table.band_size.1 {
+            return Err(format!("spawn table {} has band size {:?}, it needs at least 1 member and the smallest size first", i + 1, table.band_size))
+        }
+        if table.band_speed == 0 || table.beast_speed == 0 {
+            return Err(format!("spawn table {} has a speed of 0, so its bands or beasts would never act", i + 1))
+        }
+        if table.members.iter().map(|m| m.1).sum::<u32>() == 0 {
+            return Err(format!("spawn table {} has no member weights above 0", i + 1))
+        }
+    }
+    if tables[general[general.len() - 1]].to.is_some() {
+        return Err(String::from("the final spawn table needs to leave out its last depth, so every depth further down is covered"))
+    }
+
+    return Ok(())
+}
+
+pub fn spawn_player(data: &GameData, rng: &mut RunRng, pos: Point) -> Object {
+    let members = data.config.starting_party.iter()
+        .filter_map(|name| data.class(name))
+        .map(|class| class.make_member(data, &mut rng.names))
+        .collect();
+
     Object {
         name: String::from("Band of Heroic Elves"),
         floor: 1,
         tag: ActorTag::Player,
+        kind: ActorKind::Elves,
         pos: Some(pos),
-        render: Some(Render::new(64, ColorPair::new(GOLD1, BLACK), 255)),
-        viewshed: Some(Viewshed { range: 6, visible: Vec::new(), refresh: true }),
-        members: vec![make_guardian(), make_bard()],
+        render: Some(Render::new(64, ColorPair::new(PLAYER_COLOR, BLACK), 255)),
+        viewshed: Some(Viewshed { range: data.config.player_view_range, visible: Vec::new(), refresh: true }),
+        members,
+        //Starts out ready to go, so the player always gets the first move
+        initiative: Some(Initiative::ready(PLAYER_SPEED)),
         ..Default::default()
     }
 }
 
-pub fn spawn_band_of_forsaken(rng: &mut RandomNumberGenerator, pos: Point, f: u32) -> Object {
-    let num_enemies = rng.range(f, min(f + 1, 5));
+pub fn spawn_band_of_forsaken(data: &GameData, rng: &mut RunRng, pos: Point, f: u32) -> Object {
+    let rng = &mut rng.spawns;
+    let table = data.spawn_table(f);
+    let num_enemies = rng.range(table.band_size.0, table.band_size.1 + 1);
     let band_vec = {
         let mut vec = Vec::new();
-        for _ in 0..=num_enemies {
-            let roll = rng.roll_dice(1,10);
-            match roll {
-                1|2|3|4|5|6|7|8 => vec.push(enemy_make_forsaken_warrior(rng, f)),
-                9|10 => vec.push(enemy_make_forsaken_caster(rng, f)),
-                _ => panic!("Something's up with the dice...")
+        for _ in 0..num_enemies {
+            match table.roll_member(rng) {
+                ForsakenKind::Warrior => vec.push(enemy_make_forsaken_warrior(rng, f)),
+                ForsakenKind::Caster => vec.push(enemy_make_forsaken_caster(data, rng, f))
             }
         }
         vec
@@ -33,29 +139,32 @@ pub fn spawn_band_of_forsaken(rng: &mut RandomNumberGenerator, pos: Point, f: u3
         name: String::from("band of Forsaken Warriors"),
         floor: f,
         tag: ActorTag::Enemy,
+        kind: ActorKind::Forsaken,
         pos: Some(pos),
-        render: Some(Render::new(1, ColorPair::new(PURPLE,BLACK), 255)),
-        viewshed: Some(Viewshed { range: 6, visible: Vec::new(), refresh: true }),
+        render: Some(Render::new(1, ColorPair::new(WARRIOR_COLOR,BLACK), 255)),
+        viewshed: Some(Viewshed { range: data.config.band_view_range, visible: Vec::new(), refresh: true }),
         members: band_vec,
         ai: Some(AIClass::new()),
+        initiative: Some(Initiative::new(table.band_speed)),
         ..Default::default()
     }
 }
 
-pub fn spawn_beast(rng: &mut RandomNumberGenerator, pos: Point, f: u32) -> Object {
+pub fn spawn_beast(data: &GameData, rng: &mut RunRng, pos: Point, f: u32) -> Object {
     let hp_mod = 10 * f as i32;
     Object {
         name: String::from("Forgotten Beast"),
         floor: f,
         tag: ActorTag::Enemy,
+        kind: ActorKind::Beast,
         pos: Some(pos),
-        render: Some(Render::new(98, ColorPair::new(RED, BLACK), 255)),
-        viewshed: Some(Viewshed { range: 9, visible: Vec::new(), refresh: true }),
+        render: Some(Render::new(98, ColorPair::new(BEAST_COLOR, BLACK), 255)),
+        viewshed: Some(Viewshed { range: data.config.beast_view_range, visible: Vec::new(), refresh: true }),
         members: vec![
             PartyMember {
-                name: make_beast_name(rng),
+                name: make_beast_name(&mut rng.names),
                 class: String::from("Beast"),
-                icon: Render::new(98, ColorPair::new(RED,BLACK), 255),
+                icon: Render::new(98, ColorPair::new(BEAST_COLOR,BLACK), 255),
                 abilities: vec![],
                 health: Health::new(30 + hp_mod),
                 attack: Attack::new(4,4),
@@ -64,28 +173,21 @@ pub fn spawn_beast(rng: &mut RandomNumberGenerator, pos: Point, f: u32) -> Objec
             }
         ],
         ai: Some(AIClass::new()),
+        initiative: Some(Initiative::new(data.spawn_table(f).beast_speed)),
         ..Default::default()
     }
 }
 
-pub fn spawn_elf_pickup(rng: &mut RandomNumberGenerator, pos: Point, f: u32) -> Object {
-    let diceroll = rng.roll_dice(1, 7);
-    let member = match diceroll {
-        1 => vec![make_bard()],
-        2 => vec![make_guardian()],
-        3 => vec![make_barbarian()],
-        4 => vec![make_woodcutter()],
-        5 => vec![make_hunter()],
-        6 => vec![make_cleric()],
-        7 => vec![make_mage()],
-        _ => Vec::new()
-    };
+pub fn spawn_elf_pickup(data: &GameData, rng: &mut RunRng, pos: Point, f: u32) -> Object {
+    let class = &data.classes[rng.spawns.range(0, data.classes.len())];
+    let member = vec![class.make_member(data, &mut rng.names)];
 
     Object {
         name: String::from("Lost Elf"),
         floor: f,
         block_tile: false,
         tag: ActorTag::Elf,
+        kind: ActorKind::Elves,
         pos: Some(pos),
         render: Some(Render::new(2, ColorPair::new(WHITE, BLACK), 254)),
         viewshed: None,
@@ -94,99 +196,13 @@ pub fn spawn_elf_pickup(rng: &mut RandomNumberGenerator, pos: Point, f: u32) ->
     }
 }
 
-//Party Member definitions
-pub fn make_bard() -> PartyMember {
-    PartyMember {
-        name: format!("{}", make_random_elf_name()),
-        class: String::from("Bard"),
-        icon: Render::new(2, ColorPair::new(GOLD,BLACK), 255),
-        abilities: vec![AbilityClass::new(Ability::LesserCureWounds), AbilityClass::new(Ability::LesserMagicMissile)],
-        health: Health::new(20),
-        attack: Attack::new(2,3),
-        threat: Threat::new(4, 2),
-        modifiers: vec![],
-    }
-}
-pub fn make_guardian() -> PartyMember {
-    PartyMember {
-        name: format!("{}", make_random_elf_name()),
-        class: String::from("Guardian"),
-        icon: Render::new(2, ColorPair::new(STEEL_BLUE,BLACK), 255),
-        abilities: vec![AbilityClass::new(Ability::Taunt), AbilityClass::new(Ability::Block)],
-        health: Health::new(40),
-        attack: Attack::new(1,6),
-        threat: Threat::new(6, 3),
-        modifiers: vec![Modifier::new(ModifierEffect::Block(1), 0, true)],
-    }
-}
-pub fn make_barbarian() -> PartyMember {
-    PartyMember {
-        name: format!("{}", make_random_elf_name()),
-        class: String::from("Barbarian"),
-        icon: Render::new(2, ColorPair::new(RED,BLACK), 255),
-        abilities: vec![AbilityClass::new(Ability::RallyingCry)],
-        health: Health::new(28),
-        attack: Attack::new(2,6),
-        threat: Threat::new(7, 2),
-        modifiers: Vec::new(),
-    }
-}
-pub fn make_woodcutter() -> PartyMember {
-    PartyMember {
-        name: format!("{}", make_random_elf_name()),
-        class: String::from("Woodcutter"),
-        icon: Render::new(2, ColorPair::new(DARK_GREEN,BLACK), 255),
-        abilities: vec![AbilityClass::new(Ability::Deforest), AbilityClass::new(Ability::Cleave)],
-        health: Health::new(18),
-        attack: Attack::new(1,8),
-        threat: Threat::new(2, 4),
-        modifiers: vec![Modifier::new(ModifierEffect::PlusAttack(1), 0, true)],
-    }
-}
-pub fn make_hunter() -> PartyMember {
-    PartyMember {
-        name: format!("{}", make_random_elf_name()),
-        class: String::from("Hunter"),
-        icon: Render::new(2, ColorPair::new(SEA_GREEN,BLACK), 255),
-        abilities: vec![AbilityClass::new(Ability::KillShot)],
-        health: Health::new(16),
-        attack: Attack::new(1,6),
-        threat: Threat::new(1, 3),
-        modifiers: Vec::new(),
-    }
-}
-pub fn make_cleric() -> PartyMember {
-    PartyMember {
-        name: format!("{}", make_random_elf_name()),
-        class: String::from("Cleric"),
-        icon: Render::new(2, ColorPair::new(ANTIQUEWHITE,BLACK), 255),
-        abilities: vec![AbilityClass::new(Ability::CureWounds)],
-        health: Health::new(10),
-        attack: Attack::new(1,3),
-        threat: Threat::new(8, 0),
-        modifiers: Vec::new(),
-    }
-}
-pub fn make_mage() -> PartyMember {
-    PartyMember {
-        name: format!("{}", make_random_elf_name()),
-        class: String::from("Mage"),
-        icon: Render::new(2, ColorPair::new(CYAN,BLACK), 255),
-        abilities: vec![AbilityClass::new(Ability::MagicMissile)],
-        health: Health::new(12),
-        attack: Attack::new(1,4),
-        threat: Threat::new(2, 2),
-        modifiers: Vec::new(),
-    }
-}
-
 //Enemy member definitions
 pub fn enemy_make_forsaken_warrior(rng: &mut RandomNumberGenerator, f: u32) -> PartyMember {
     let hp_mod = rng.range(1, f as i32 + 3);
     PartyMember {
         name: String::from("Forsaken Elf"),
         class: String::from("Warrior"),
-        icon: Render::new(1, ColorPair::new(PURPLE,BLACK), 255),
+        icon: Render::new(1, ColorPair::new(WARRIOR_COLOR,BLACK), 255),
         abilities: vec![],
         health: Health::new(10 + hp_mod),
         attack: Attack::new(1,rng.range(4, min(4 + (f / 2) as i32, 8) + 1)),
@@ -194,13 +210,13 @@ pub fn enemy_make_forsaken_warrior(rng: &mut RandomNumberGenerator, f: u32) -> P
         modifiers: Vec::new(),
     }
 }
-pub fn enemy_make_forsaken_caster(rng: &mut RandomNumberGenerator, f: u32) -> PartyMember {
+pub fn enemy_make_forsaken_caster(data: &GameData, rng: &mut RandomNumberGenerator, f: u32) -> PartyMember {
     let hp_mod = rng.range(1, f as i32 + 3);
     PartyMember {
         name: String::from("Dark Magus"),
-        class: String::from("Psychomancer"),
-        icon: Render::new(1, ColorPair::new(BLUE_VIOLET,BLACK), 255),
-        abilities: vec![AbilityClass::new(Ability::PsyBolt)],
+        class: String::from(CASTER_CLASS),
+        icon: Render::new(1, ColorPair::new(CASTER_COLOR,BLACK), 255),
+        abilities: data.ability(CASTER_ABILITY).map(AbilityClass::new).into_iter().collect(),
         health: Health::new(5 + hp_mod),
         attack: Attack::new(1,rng.range(2, min(2 + (f / 2) as i32, 4) + 1)),
         threat: Threat::new(2, 0),
@@ -240,4 +256,72 @@ fn make_beast_name(rng: &mut RandomNumberGenerator) -> String {
         else {"!!!"};
 
     return String::from(format!("{}{}", string1, string2))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table_with(members: Vec<(ForsakenKind, u32)>) -> SpawnTable {
+        SpawnTable {
+            from: 1, to: None, biome: None,
+            bands: 1, band_size: (1, 1), members,
+            beasts: 0, elves: 0,
+            band_speed: PLAYER_SPEED, beast_speed: PLAYER_SPEED
+        }
+    }
+    fn table(from: u32, to: Option<u32>, biome: Option<&str>) -> SpawnTable {
+        SpawnTable { from, to, biome: biome.map(String::from), ..table_with(vec![(ForsakenKind::Warrior, 1)]) }
+    }
+
+    #[test]
+    fn single_kind_is_always_picked() {
+        let table = table_with(vec![(ForsakenKind::Caster, 3)]);
+        let mut rng = RandomNumberGenerator::seeded(1);
+        assert!((0..1000).all(|_| table.roll_member(&mut rng) == ForsakenKind::Caster));
+    }
+
+    #[test]
+    fn zero_weight_kind_is_never_picked() {
+        let table = table_with(vec![(ForsakenKind::Warrior, 0), (ForsakenKind::Caster, 5)]);
+        let mut rng = RandomNumberGenerator::seeded(2);
+        assert!((0..1000).all(|_| table.roll_member(&mut rng) == ForsakenKind::Caster));
+    }
+
+    #[test]
+    fn kinds_are_picked_in_proportion_to_their_weights() {
+        let table = table_with(vec![(ForsakenKind::Warrior, 8), (ForsakenKind::Caster, 2)]);
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let warriors = (0..10000).filter(|_| table.roll_member(&mut rng) == ForsakenKind::Warrior).count();
+        assert!(warriors > 7700 && warriors < 8300, "{} warriors out of 10000", warriors);
+    }
+
+    #[test]
+    fn contiguous_tables_are_accepted() {
+        let tables = vec![table(1, Some(2), None), table(3, None, None), table(3, Some(4), Some("Blighted Wood"))];
+        assert!(validate_spawn_tables(&tables).is_ok());
+    }
+
+    #[test]
+    fn gap_between_tables_is_rejected() {
+        let tables = vec![table(1, Some(2), None), table(4, None, None)];
+        assert!(validate_spawn_tables(&tables).is_err());
+    }
+
+    #[test]
+    fn overlapping_tables_are_rejected() {
+        let tables = vec![table(1, Some(3), None), table(2, None, None)];
+        assert!(validate_spawn_tables(&tables).is_err());
+    }
+
+    #[test]
+    fn final_table_has_to_be_open_ended() {
+        let tables = vec![table(1, Some(2), None), table(3, Some(9), None)];
+        assert!(validate_spawn_tables(&tables).is_err());
+    }
+
+    #[test]
+    fn zero_member_weights_are_rejected() {
+        let tables = vec![table_with(vec![(ForsakenKind::Warrior, 0), (ForsakenKind::Caster, 0)])];
+        assert!(validate_spawn_tables(&tables).is_err());
+    }
+}
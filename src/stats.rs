@@ -0,0 +1,92 @@
+use crate::prelude::*;
+
+//Running totals for the run, kept up to date from the event queue
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RunStats {
+    pub beast_kills: u32,
+    pub forsaken_kills: u32,
+    pub rescued_elves: u32,
+    //What finished off the party, once it's gone
+    pub killed_by: Option<String>,
+    //Every elf who ever joined the party, in the order they joined
+    pub roster: Vec<RosterEntry>,
+    //Classes of the last party that still had anyone in it, so a wiped party can still be told apart
+    pub final_party: Vec<String>
+}
+impl RunStats {
+    pub fn new() -> RunStats { RunStats::default() }
+
+    pub fn add_to_roster(&mut self, member: &PartyMember, turn: u32, depth: u32) {
+        self.roster.push(RosterEntry { name: member.name.clone(), class: member.class.clone(), joined_turn: turn, joined_depth: depth, death: None });
+    }
+
+    pub fn tally_events(&mut self, events: &EventQueue, turn: u32, depth: u32) {
+        for event in events.iter() {
+            match event {
+                //Only enemies count as kills, losing your own elves doesn't
+                GameEvent::MemberSlain { victim, side: ActorTag::Enemy, .. } => {
+                    if victim.kind == ActorKind::Beast { self.beast_kills += 1 }
+                    else { self.forsaken_kills += 1 }
+                }
+                GameEvent::MemberSlain { victim, side: ActorTag::Player, killer } => {
+                    //Names can repeat, so it's whoever by that name joined most recently and is still standing
+                    let entry = self.roster.iter_mut().rev()
+                        .find(|e| e.death.is_none() && e.name == victim.name && e.class == victim.class);
+                    if let Some(entry) = entry {
+                        entry.death = Some(RosterDeath { turn, depth, killer: killer.as_ref().map(|k| k.name.clone()) });
+                    }
+                }
+                GameEvent::ElfRecruited { elf } => {
+                    self.rescued_elves += 1;
+                    self.roster.push(RosterEntry { name: elf.name.clone(), class: elf.class.clone(), joined_turn: turn, joined_depth: depth, death: None });
+                }
+                GameEvent::PlayerPartyWiped { killer } => {
+                    self.killed_by = Some(killer.as_ref().map_or(String::from("unknown causes"), |k| k.name.clone()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RosterEntry {
+    pub name: String,
+    pub class: String,
+    pub joined_turn: u32,
+    pub joined_depth: u32,
+    pub death: Option<RosterDeath>
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RosterDeath {
+    pub turn: u32,
+    pub depth: u32,
+    pub killer: Option<String>
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slain(name: &str, class: &str, kind: ActorKind) -> GameEvent {
+        let victim = Combatant { name: String::from(name), class: String::from(class), kind };
+        return GameEvent::MemberSlain { victim, side: ActorTag::Enemy, killer: None }
+    }
+
+    #[test]
+    fn kills_are_counted_by_what_was_killed() {
+        let mut stats = RunStats::new();
+        let events = vec![
+            slain("Grax", "Beast", ActorKind::Beast),
+            slain("Forsaken Elf", "Warrior", ActorKind::Forsaken),
+            slain("Dark Magus", CASTER_CLASS, ActorKind::Forsaken),
+            //Going by the kind means a Forsaken that happens to share a beast's class name is still a Forsaken
+            slain("Odd One", "Beast", ActorKind::Forsaken)
+        ];
+        stats.tally_events(&events, 1, 1);
+
+        assert_eq!(stats.beast_kills, 1);
+        assert_eq!(stats.forsaken_kills, 3);
+    }
+}
@@ -1,28 +1,20 @@
 use crate::prelude::*;
 
-pub fn process_ai(objects: &mut Vec<Object>, map: &mut Map, floor: u32, rng: &mut RandomNumberGenerator, logs: &mut LogBuffer) {
-    let (player, all) = objects.split_at_mut(1);
-    let player_pos = player[0].pos.unwrap();
-    let mut proclist = Vec::new();
+/// Lets a single enemy party take its turn: moving, attacking or using abilities.
+pub fn process_enemy_turn(enemy: EntityId, objects: &mut Vec<Object>, map: &mut Map, data: &GameData, floor: u32, rng: &mut RandomNumberGenerator, events: &mut EventQueue) {
+    let player_pos = objects[0].pos.unwrap();
 
-    for (id, obj) in all.iter().enumerate() {
-        if let Object{ tag, .. } = obj {
-            if *tag == ActorTag::Enemy {
-                proclist.push(id);
-            }
-        }
-    }
-
-    for unit in proclist.iter() {
-        basic_enemy_ai(*unit, objects, map, rng, logs, player_pos);
-        run_ai_abilities(objects, *unit, logs, rng);
+    if let Some(idx) = objects.index_of(enemy) {
+        if objects[idx].tag != ActorTag::Enemy { return }
+        basic_enemy_ai(idx, objects, map, rng, events, player_pos);
+        run_ai_abilities(objects, map, data, idx, events, rng);
         update_blocked_tiles(objects, map, floor);
     }
 }
 
-fn basic_enemy_ai(enemy_id: usize, objects: &mut Vec<Object>, map: &mut Map, rng: &mut RandomNumberGenerator, logs: &mut LogBuffer, player_pos: Point) {
+fn basic_enemy_ai(enemy_id: usize, objects: &mut Vec<Object>, map: &mut Map, rng: &mut RandomNumberGenerator, events: &mut EventQueue, player_pos: Point) {
     let (player, all) = &mut objects.split_at_mut(1);
-    let enemy = &mut all[enemy_id];
+    let enemy = &mut all[enemy_id - 1];
     let player = &mut player[0];
     let pos = enemy.pos.unwrap();
 
@@ -32,7 +24,7 @@ fn basic_enemy_ai(enemy_id: usize, objects: &mut Vec<Object>, map: &mut Map, rng
                 enemy.in_combat = true;
                 player.in_combat = true;
 
-                ai.target = Some(0);
+                ai.target = Some(player.id);
                 ai.state = AIState::Chasing;
                 ai.tgt_memory = 24;
                 ai.tgt_heatmap.reset_to_single_node(&player_pos);
@@ -40,7 +32,7 @@ fn basic_enemy_ai(enemy_id: usize, objects: &mut Vec<Object>, map: &mut Map, rng
                 let mut dest: Point = pos;
                 let distance = DistanceAlg::Pythagoras.distance2d(pos, player_pos);
                 let targets = vec![map.point2d_to_index(player_pos)];
-                let dijkstra_map = DijkstraMap::new(90, 90, &targets, map, 1024.0);
+                let dijkstra_map = DijkstraMap::new(map.width, map.height, &targets, map, 1024.0);
 
                 if let Some(destidx) = DijkstraMap::find_lowest_exit(&dijkstra_map, map.point2d_to_index(pos), map) {
                     dest = if distance > 1.45 {
@@ -50,7 +42,7 @@ fn basic_enemy_ai(enemy_id: usize, objects: &mut Vec<Object>, map: &mut Map, rng
                     };
                 }
                 if distance <= 1.45 {
-                    enemy.try_attack(player, 0, rng, logs);
+                    enemy.try_attack(player, rng, events);
                 } else if dest != pos { enemy.try_move(dest, map) }
             } else if ai.tgt_memory > 0 {
                 enemy.in_combat = false;
@@ -76,12 +68,12 @@ fn clear_ai_heatmap(enemy: &mut Object) {
 }
 
 
-fn run_ai_abilities(objects: &mut Vec<Object>, enemy_id: usize, logs: &mut LogBuffer, rng: &mut RandomNumberGenerator) {
+fn run_ai_abilities(objects: &mut Vec<Object>, map: &mut Map, data: &GameData, enemy_id: usize, events: &mut EventQueue, rng: &mut RandomNumberGenerator) {
     let mut ability_vec = Vec::new();
 
     let mut target = None;
     if let Some(ai) = &objects[enemy_id].ai {
-        if let Some(tgt) = ai.target {
+        if let Some(tgt) = ai.target.and_then(|id| objects.index_of(id)) {
             target = Some(tgt);
             for (i, member) in objects[enemy_id].members.iter_mut().enumerate() {
                 if !member.abilities.is_empty() {
@@ -103,14 +95,11 @@ fn run_ai_abilities(objects: &mut Vec<Object>, enemy_id: usize, logs: &mut LogBu
         }
     }
     for a in ability_vec.iter() {
-        let mut success = false;
-        {
-            let ability = objects[enemy_id].members[a.0].abilities[a.1].ability.clone();
-            success = match ability {
-                Ability::PsyBolt => run_psybolt(objects, target, (enemy_id, a.0), logs, rng),
-                _ => false
-            };
-        }
+        //Failed attempts just mean the enemy tries again next turn, there's no one to tell about them
+        let success = match data.ability(&objects[enemy_id].members[a.0].abilities[a.1].ability) {
+            Some(def) => use_ability(objects, map, def, (enemy_id, a.0), target, rng, events).is_ok(),
+            None => false
+        };
         {
             let ability = &mut objects[enemy_id].members[a.0].abilities[a.1];
             if success { ability.set_on_cooldown() }
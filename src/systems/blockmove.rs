@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+/// Marks the tiles occupied by blocking objects on this floor.
 pub fn update_blocked_tiles(objects: &Vec<Object>, map: &mut Map, floor: u32) {
     for b in map.obj_blocked.iter_mut() {
         *b = false;
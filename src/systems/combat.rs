@@ -1,18 +1,22 @@
 use crate::prelude::*;
+use std::collections::HashMap;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct TargetedAttack {
-    pub target: (usize, usize),
+    pub target: (EntityId, usize),
     pub damage: i32
 }
 impl TargetedAttack {
-    pub fn new(target: (usize, usize), damage: i32) -> TargetedAttack { TargetedAttack { target, damage } }
+    pub fn new(target: (EntityId, usize), damage: i32) -> TargetedAttack { TargetedAttack { target, damage } }
 }
 
-pub fn process_combat(objects: &mut Vec<Object>, logs: &mut LogBuffer, player_death: &mut bool, player_targets: &mut TargetList, map: &Map, fkills: &mut u32, bkills: &mut u32) {
+/// Resolves queued attacks, removes the dead and reports it all as events. Sets `player_death` once the player's party is gone.
+pub fn process_combat(objects: &mut Vec<Object>, entities: &mut EntityAllocator, events: &mut EventQueue, player_death: &mut bool, player_targets: &mut TargetList, map: &Map) {
     let mut attack_list: Vec<(usize, TargetedAttack)> = Vec::new();
-    let mut kill_list: Vec<(usize, usize)> = Vec::new();
-    let mut check_ai_list: Vec<(usize, usize)> = Vec::new();
+    let mut kill_list: Vec<(EntityId, usize)> = Vec::new();
+    let mut check_ai_list: Vec<(usize, EntityId)> = Vec::new();
+    //Whoever landed the last hit on each member, for telling how they died
+    let mut killers: HashMap<(EntityId, usize), Combatant> = HashMap::new();
     let mut refresh_targets = false;
 
     //Save the targeted attack and the ID of the object triggering it
@@ -35,11 +39,12 @@ pub fn process_combat(objects: &mut Vec<Object>, logs: &mut LogBuffer, player_de
         }
     }
 
+    let player_id = objects[0].id;
     for c in check_ai_list.iter() {
         let player_pos = &objects[0].pos.as_ref().unwrap().clone();
-        if let Some(ai) = &mut objects[c.1].ai {
+        if let Some(Object { ai: Some(ai), .. }) = objects.get_by_id_mut(c.1) {
             if c.0 == 0 {
-                ai.target = Some(0);
+                ai.target = Some(player_id);
                 ai.state = AIState::Chasing;
                 ai.tgt_memory = 24;
                 ai.tgt_heatmap.reset_to_single_node(player_pos);
@@ -53,43 +58,45 @@ pub fn process_combat(objects: &mut Vec<Object>, logs: &mut LogBuffer, player_de
     }
     //Process the damage against the targeted party member's health
     for a in attack_list.iter() {
-        let target = &mut objects[a.1.target.0].members[a.1.target.1];
-        target.health.lose_life(a.1.damage);
+        let mut slain = false;
+        if let Some(target_obj) = objects.get_by_id_mut(a.1.target.0) {
+            if let Some(target) = target_obj.members.get_mut(a.1.target.1) {
+                target.health.lose_life(a.1.damage);
 
-        if target.health.get_life() <= 0 {
-            kill_list.push((a.1.target.0, a.1.target.1));
+                if target.health.get_life() <= 0 {
+                    kill_list.push((a.1.target.0, a.1.target.1));
+                    slain = true;
+                }
+            }
         }
+        if slain { killers.insert(a.1.target, Combatant::from_object(&objects[a.0])); }
     }
-    //Kill anything that was added to the kill list
+    //Kill anything that was added to the kill list, highest member index first so the others don't shift underneath us
+    kill_list.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
     kill_list.dedup();
     for k in kill_list.iter() {
-        let object_party = &mut objects[k.0].members;
-
-        logs.update_logs(LogMessage::new()
-            .add_part(format!("{}", object_party[k.1].name), ColorPair::new(object_party[k.1].icon.get_render().1.fg, GREY10))
-            .add_part("has been slain.", ColorPair::new(WHITE, GREY10))
-        );
-
-        if object_party[k.1].class == "Beast" {
-            *bkills += 1
-        }
-        else {
-            *fkills += 1
-        }
+        let obj_idx = match objects.index_of(k.0) {
+            Some(idx) => idx,
+            None => continue
+        };
+        let side = objects[obj_idx].tag;
+        let kind = objects[obj_idx].kind;
+        let killer = killers.get(k).cloned();
+        let object_party = &mut objects[obj_idx].members;
+        events.push(GameEvent::MemberSlain { victim: Combatant::from_member(&object_party[k.1], kind), side, killer: killer.clone() });
 
         object_party.remove(k.1);
         object_party.shrink_to_fit();
 
         //Remove the whole object if the party is empty (but also not the player)
         if object_party.is_empty() {
-            if objects[k.0].tag != ActorTag::Player {
+            if objects[obj_idx].tag != ActorTag::Player {
                 refresh_targets = true;
-                logs.update_logs(LogMessage::new()
-                    .add_part("You have defeated the", ColorPair::new(WHITE, GREY10))
-                    .add_part(format!("{}.", objects[k.0].name), ColorPair::new(objects[k.0].render.as_ref().unwrap().get_render().1.fg, GREY10))
-                );
-                objects.remove(k.0);
+                events.push(GameEvent::PartyDefeated { party: Combatant::from_object(&objects[obj_idx]) });
+                entities.free(k.0);
+                objects.remove(obj_idx);
             } else {
+                if !*player_death { events.push(GameEvent::PlayerPartyWiped { killer }); }
                 *player_death = true;
             }
         }
@@ -97,11 +104,12 @@ pub fn process_combat(objects: &mut Vec<Object>, logs: &mut LogBuffer, player_de
 
     //Mark off the player as no longer in combat if there are no enemies around them
     let player_view = objects[0].viewshed.as_ref().unwrap().visible.to_vec();
+    let player_floor = objects[0].floor;
     let mut still_in_combat = {
         let mut result = false;
         for obj in objects.iter() {
-            if let Object { pos: Some(pos), tag, .. } = obj {
-                if player_view.contains(pos) && tag == &ActorTag::Enemy {
+            if let Object { pos: Some(pos), tag, floor, .. } = obj {
+                if player_view.contains(pos) && tag == &ActorTag::Enemy && *floor == player_floor {
                     result = true
                 }
             }
@@ -113,12 +121,14 @@ pub fn process_combat(objects: &mut Vec<Object>, logs: &mut LogBuffer, player_de
     if refresh_targets { player_targets.reset_targets(objects, map) }
 }
 
+/// Lets every member attack again at the start of a turn.
 pub fn reset_attack_capabilities(members: &mut Vec<PartyMember>) {
     for member in members.iter_mut() {
         member.attack.enable_attack();
     }
 }
 
+/// Ticks every ability cooldown forward one turn.
 pub fn process_all_cooldowns(objects: &mut Vec<Object>) {
     for obj in objects.iter_mut() {
         for member in obj.members.iter_mut() {
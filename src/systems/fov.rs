@@ -1,9 +1,10 @@
 use crate::prelude::*;
 
-pub fn process_fov(objects: &mut Vec<Object>, map: &mut Map) {
+/// Recalculates what each object with a viewshed on this floor can see.
+pub fn process_fov(objects: &mut Vec<Object>, map: &mut Map, floor: u32) {
     let mut fovlist: Vec<usize> = Vec::new();
     for (i, obj) in objects.iter().enumerate() {
-        if obj.viewshed.is_some() {
+        if obj.viewshed.is_some() && obj.floor == floor {
             fovlist.push(i);
         }
     }
@@ -7,6 +7,7 @@ mod player_collide;
 mod ai_system;
 mod targeting;
 mod modifier_handler;
+mod scheduler;
 
 pub use fov::*;
 pub use blockmove::*;
@@ -16,4 +17,5 @@ pub use party_add::*;
 pub use player_collide::*;
 pub use ai_system::*;
 pub use targeting::*;
-pub use modifier_handler::*;
\ No newline at end of file
+pub use modifier_handler::*;
+pub use scheduler::*;
\ No newline at end of file
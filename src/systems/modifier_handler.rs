@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+/// Applies every active modifier to its member's stats.
 pub fn apply_party_modifiers(party: &mut Vec<PartyMember>) {
     for member in party.iter_mut() {
         if !member.modifiers.is_empty() {
@@ -18,6 +19,7 @@ pub fn apply_party_modifiers(party: &mut Vec<PartyMember>) {
     }
 }
 
+/// Ticks modifiers down and removes the ones that have run out.
 pub fn clean_party_modifiers(party: &mut Vec<PartyMember>) {
     for member in party.iter_mut() {
         if !member.modifiers.is_empty() {
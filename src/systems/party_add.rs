@@ -1,5 +1,6 @@
 use crate::prelude::*;
 
+/// Adds a rescued elf to the player's party.
 pub fn add_member_to_party(elf: PartyMember, player: &mut Object) {
     player.members.push(elf.clone());
 }
\ No newline at end of file
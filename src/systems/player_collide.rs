@@ -1,24 +1,34 @@
 use crate::prelude::*;
 
-pub fn check_player_collisions(gs: &mut State) {
+/// Handles whatever the player is standing on: portals and lost elves.
+pub fn check_player_collisions(gs: &mut Game) {
     let pos = grab_position(&gs.world.objects);
     let idx = gs.world.map.point2d_to_index(pos);
 
-    //Check if the player stepped on the portal
-    if gs.world.map.tiles[idx] == TileClass::ForestPortal {
-        gs.go_next_level = true;
-        return
+    //Check if the player stepped on either portal
+    match gs.world.map.tiles[idx] {
+        TileClass::ForestPortal => {
+            gs.events.push(GameEvent::PortalEntered { depth: gs.world.depth + 1 });
+            gs.travel_to = Some(gs.world.depth + 1);
+            return
+        }
+        TileClass::ReturnPortal => {
+            gs.events.push(GameEvent::ReturnPortalEntered { depth: gs.world.depth - 1 });
+            gs.travel_to = Some(gs.world.depth - 1);
+            return
+        }
+        _ => {}
     }
 
     //Check if the player is trying to pick up some elves
     let mut maybe_add: Vec<PartyMember> = Vec::new();
-    let mut remove_list: Vec<usize> = Vec::new();
-    for (i, obj) in gs.world.objects.iter().enumerate() {
-        if let Object { pos: Some(obj_pos), members: members, tag: ActorTag::Elf, .. } = obj {
-            if pos == *obj_pos {
+    let mut remove_list: Vec<EntityId> = Vec::new();
+    for obj in gs.world.objects.iter() {
+        if let Object { pos: Some(obj_pos), members: members, tag: ActorTag::Elf, id, floor, .. } = obj {
+            if pos == *obj_pos && *floor == gs.world.depth {
                 for member in members {
                     maybe_add.push(member.clone());
-                    remove_list.push(i);
+                    remove_list.push(*id);
                 }
             }
         }
@@ -28,23 +38,20 @@ pub fn check_player_collisions(gs: &mut State) {
         for e in maybe_add.iter() {
             let player = &mut gs.world.objects[0];
 
-            if player.members.len() >= 10 {
-                gs.logs.update_logs(LogMessage::new()
-                    .add_part("Your party is full!", ColorPair::new(WHITE, GREY10))
-                );
+            if player.members.len() >= gs.world.data.config.party_cap {
+                gs.events.push(GameEvent::PartyFull);
                 return
             }
             else {
                 add_member_to_party(e.clone(), player);
-                gs.logs.update_logs(LogMessage::new()
-                    .add_part(format!("{}, the {}", e.name, e.class), ColorPair::new(e.icon.get_render().1.fg, GREY10))
-                    .add_part("has joined the party!", ColorPair::new(WHITE, GREY10))
-                );
-                gs.rescued_elves += 1;
+                gs.events.push(GameEvent::ElfRecruited { elf: Combatant::from_member(e, ActorKind::Elves) });
             }
         }
         for r in remove_list.iter() {
-            gs.world.objects.remove(*r);
+            if let Some(idx) = gs.world.index_of(*r) {
+                gs.world.objects.remove(idx);
+                gs.world.entities.free(*r);
+            }
         }
 
         gs.player_targets.reset_targets(&gs.world.objects, &gs.world.map);
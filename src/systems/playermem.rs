@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
+/// Remembers where the player last saw each object on their floor.
 pub fn update_player_memory(objects: &mut Vec<Object>) {
+    let floor = objects[0].floor;
     let visible =
         if let Some(view) = &objects[0].viewshed {
             view.visible.to_vec()
@@ -10,6 +12,7 @@ pub fn update_player_memory(objects: &mut Vec<Object>) {
         };
 
     for obj in objects.iter_mut() {
+        if obj.floor != floor { continue }
         if let Object { pos: Some(pos), player_mem: mem, .. } = obj {
             //Clear out the player memory if that spot has been seen again
             if mem.last_pos.is_some() {
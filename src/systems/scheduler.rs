@@ -0,0 +1,101 @@
+use crate::prelude::*;
+
+/// Lists every object on this floor whose initiative has come due, most energy first. The player wins any ties.
+pub fn due_actors(objects: &Vec<Object>, floor: u32) -> InitList {
+    let mut list = InitList::new();
+    for obj in objects.iter() {
+        if let Object { initiative: Some(init), id, .. } = obj {
+            if init.is_due() && obj.floor == floor { list.add_object(*id, init.energy.max(0) as u32) }
+        }
+    }
+    list.sort();
+    return list
+}
+
+/// Moves time forward a tick, building up the energy of everyone on this floor by their speed.
+/// Floors the player isn't on stay frozen until they come back.
+pub fn tick_initiative(objects: &mut Vec<Object>, floor: u32) {
+    for obj in objects.iter_mut() {
+        if obj.floor != floor { continue }
+        if let Some(init) = &mut obj.initiative { init.gain_energy() }
+    }
+}
+
+pub fn spend_initiative(objects: &mut Vec<Object>, id: EntityId) {
+    if let Some(Object { initiative: Some(init), .. }) = objects.get_by_id_mut(id) {
+        init.spend_energy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Everything here lives on floor 1, with the player first in the list like it is in the game
+    fn actors(speeds: &[u32], entities: &mut EntityAllocator) -> Vec<Object> {
+        return speeds.iter()
+            .map(|speed| Object { id: entities.allocate(), floor: 1, initiative: Some(Initiative::ready(*speed)), ..Default::default() })
+            .collect()
+    }
+
+    //The player spends their turn, then everyone else acts the way Game::run_schedule has them, until the
+    //player is due again. Hands back who acted, in order.
+    fn player_turn(objects: &mut Vec<Object>) -> Vec<EntityId> {
+        let player = objects[0].id;
+        spend_initiative(objects, player);
+
+        let mut acted = Vec::new();
+        loop {
+            match due_actors(objects, 1).first() {
+                None => tick_initiative(objects, 1),
+                Some(due) if due.0 == player => return acted,
+                Some(due) => {
+                    let id = due.0;
+                    acted.push(id);
+                    spend_initiative(objects, id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fast_beast_acts_twice_a_turn() {
+        let mut objects = actors(&[PLAYER_SPEED, 200], &mut EntityAllocator::new());
+        let beast = objects[1].id;
+        for turn in 0..10 {
+            let acted = player_turn(&mut objects);
+            assert_eq!(acted, vec![beast, beast], "turn {}", turn);
+        }
+    }
+
+    #[test]
+    fn slow_band_acts_every_other_turn() {
+        let mut objects = actors(&[PLAYER_SPEED, 50], &mut EntityAllocator::new());
+        let counts: Vec<usize> = (0..10).map(|_| player_turn(&mut objects).len()).collect();
+        assert_eq!(counts, vec![1, 0, 1, 0, 1, 0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn player_wins_ties() {
+        let mut objects = actors(&[PLAYER_SPEED, PLAYER_SPEED, PLAYER_SPEED, PLAYER_SPEED], &mut EntityAllocator::new());
+        assert!(objects.iter().all(|o| o.initiative.as_ref().unwrap().energy == objects[0].initiative.as_ref().unwrap().energy));
+        assert_eq!(due_actors(&objects, 1)[0].0, objects[0].id);
+
+        //Everyone else then gets exactly one go before the player comes round again
+        for _ in 0..5 {
+            let acted = player_turn(&mut objects);
+            let others: Vec<EntityId> = objects[1..].iter().map(|o| o.id).collect();
+            assert_eq!(acted, others);
+        }
+    }
+
+    #[test]
+    fn rough_ground_delays_the_next_action() {
+        let mut objects = actors(&[PLAYER_SPEED, PLAYER_SPEED], &mut EntityAllocator::new());
+        //The band's first action is a step over a fallen log, the same cost try_move would set
+        objects[1].initiative.as_mut().unwrap().action_cost = TileClass::FallenLog.move_cost().unwrap();
+
+        let counts: Vec<usize> = (0..4).map(|_| player_turn(&mut objects).len()).collect();
+        assert_eq!(counts, vec![1, 0, 0, 1]);
+    }
+}
@@ -1,20 +1,25 @@
 use crate::prelude::*;
 
-pub fn update_targets_in_vision(gs: &mut State) {
-    if let Some(tgt_idx) = gs.player_targets.get_current_target().clone() {
+/// Drops the current target once it's gone or out of sight.
+pub fn update_targets_in_vision(gs: &mut Game) {
+    if let Some(tgt_id) = gs.player_targets.get_current_target().clone() {
         let visible = gs.world.objects[0].viewshed.as_ref().unwrap().visible.to_vec();
-        let target_pos = gs.world.objects[tgt_idx].pos.as_ref().unwrap().clone();
+        let target_pos = gs.world.get_object(tgt_id).and_then(|o| o.pos);
 
-        if !visible.contains(&target_pos) { gs.player_targets.reset_targets(&gs.world.objects, &gs.world.map); }
+        match target_pos {
+            Some(pos) if visible.contains(&pos) => {},
+            _ => gs.player_targets.reset_targets(&gs.world.objects, &gs.world.map)
+        }
     }
     if gs.player_targets.possible_targets.is_empty() {
         gs.player_targets.reset_targets(&gs.world.objects, &gs.world.map);
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct TargetList {
     current_target_index: Option<usize>,
-    possible_targets: Vec<(usize, f32)>
+    possible_targets: Vec<(EntityId, f32)>
 }
 impl TargetList {
     pub fn new() -> TargetList {
@@ -23,7 +28,7 @@ impl TargetList {
             possible_targets: Vec::new()
         }
     }
-    pub fn get_current_target(&self) -> Option<usize> {
+    pub fn get_current_target(&self) -> Option<EntityId> {
         return if let Some(idx) = self.current_target_index {
             Some(self.possible_targets[idx].0)
         } else {
@@ -54,11 +59,11 @@ impl TargetList {
             }
         }
     }
-    pub fn set_possible_targets(&mut self, target_list: Vec<(usize, f32)>) { self.possible_targets = target_list }
+    pub fn set_possible_targets(&mut self, target_list: Vec<(EntityId, f32)>) { self.possible_targets = target_list }
     pub fn num_targets(&self) -> usize { self.possible_targets.len() }
 }
 
-fn check_for_targets(objects: &Vec<Object>, map: &Map) -> Vec<(usize, f32)> {
+fn check_for_targets(objects: &Vec<Object>, map: &Map) -> Vec<(EntityId, f32)> {
     let player_pos = objects[0].pos.as_ref().unwrap().clone();
     let (enemy_positions, friend_positions) = grab_all_positions(objects);
 
@@ -77,19 +82,19 @@ fn check_for_targets(objects: &Vec<Object>, map: &Map) -> Vec<(usize, f32)> {
     return target_list
 }
 
-fn grab_all_positions(objects: &Vec<Object>) -> (Vec<(usize, Point)>, Vec<(usize, Point)>) {
+fn grab_all_positions(objects: &Vec<Object>) -> (Vec<(EntityId, Point)>, Vec<(EntityId, Point)>) {
     let vis_area = objects[0].viewshed.as_ref().unwrap().visible.to_vec();
-    let mut enemy_positions: Vec<(usize, Point)> = Vec::new();
-    let mut friend_positions: Vec<(usize, Point)> = Vec::new();
+    let mut enemy_positions: Vec<(EntityId, Point)> = Vec::new();
+    let mut friend_positions: Vec<(EntityId, Point)> = Vec::new();
 
-    for (i, obj) in objects.iter().enumerate() {
-        if let Object { pos: Some(pos), tag, .. } = obj {
-            if vis_area.contains(pos) {
+    for obj in objects.iter() {
+        if let Object { pos: Some(pos), tag, id, floor, .. } = obj {
+            if vis_area.contains(pos) && *floor == objects[0].floor {
                 if tag == &ActorTag::Enemy {
-                    enemy_positions.push((i, *pos));
+                    enemy_positions.push((*id, *pos));
                 }
                 else if tag == &ActorTag::Elf {
-                    friend_positions.push((i, *pos));
+                    friend_positions.push((*id, *pos));
                 }
             }
         }
@@ -98,12 +103,12 @@ fn grab_all_positions(objects: &Vec<Object>) -> (Vec<(usize, Point)>, Vec<(usize
     return (enemy_positions, friend_positions)
 }
 
-fn find_distance_of_positions(pos_vec: Vec<(usize, Point)>, map: &Map, player_pos: Point) -> Vec<(usize, f32)> {
-    let mut distance_list: Vec<(usize, f32)> = Vec::new();
-    let id_list: Vec<usize> = pos_vec.iter().map(|a| a.0).collect();
+fn find_distance_of_positions(pos_vec: Vec<(EntityId, Point)>, map: &Map, player_pos: Point) -> Vec<(EntityId, f32)> {
+    let mut distance_list: Vec<(EntityId, f32)> = Vec::new();
+    let id_list: Vec<EntityId> = pos_vec.iter().map(|a| a.0).collect();
     let pos_list: Vec<Point> = pos_vec.iter().map(|a| a.1).collect();
 
     return pos_list.iter().enumerate()
         .map(|(i, pos)| (id_list[i], DistanceAlg::Pythagoras.distance2d(player_pos, *pos)))
-        .collect::<Vec<(usize, f32)>>()
+        .collect::<Vec<(EntityId, f32)>>()
 }
\ No newline at end of file
@@ -0,0 +1,181 @@
+use crate::prelude::*;
+
+//How many abilities the sidebar has keys for: the number keys, then the number keys again with Shift or Alt
+pub const ABILITY_SLOTS: usize = 20;
+
+/// Everything the player can do with a keypress. Each one is fed to [`Game::step`] and recorded in the replay.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Actions {
+    MoveUp,MoveDown,MoveLeft,MoveRight,
+    MoveUpLeft,MoveUpRight,MoveDownLeft,MoveDownRight,
+    Wait, CycleTarget,
+    ShowHelp,
+
+    //Uses the ability in this slot of the sidebar, counting from 0
+    UseAbility(usize)
+}
+impl Actions {
+    /// The move for a single step in the direction of `delta`, if it is one.
+    pub fn step(delta: Point) -> Option<Actions> {
+        return match (delta.x, delta.y) {
+            (-1, 0) => Some(Actions::MoveLeft),
+            (1, 0) => Some(Actions::MoveRight),
+            (0, -1) => Some(Actions::MoveUp),
+            (0, 1) => Some(Actions::MoveDown),
+            (-1, -1) => Some(Actions::MoveUpLeft),
+            (1, -1) => Some(Actions::MoveUpRight),
+            (-1, 1) => Some(Actions::MoveDownLeft),
+            (1, 1) => Some(Actions::MoveDownRight),
+            _ => None
+        }
+    }
+    /// The action that uses the ability in this slot of the sidebar, counting from 0.
+    pub fn use_ability(slot: usize) -> Option<Actions> {
+        return if slot < ABILITY_SLOTS { Some(Actions::UseAbility(slot)) } else { None }
+    }
+}
+
+/// Carries out one player action. Sets `passed_turn` if it used up the player's turn.
+pub fn process_action(gs: &mut Game, action: Actions) {
+    let result = match action {
+        Actions::MoveLeft => try_move_player(gs, DL_LEFT),
+        Actions::MoveRight => try_move_player(gs, DL_RIGHT),
+        Actions::MoveUp => try_move_player(gs, DL_UP),
+        Actions::MoveDown => try_move_player(gs, DL_DOWN),
+
+        Actions::MoveUpLeft => try_move_player(gs, DL_UP + DL_LEFT),
+        Actions::MoveUpRight => try_move_player(gs, DL_UP + DL_RIGHT),
+        Actions::MoveDownLeft => try_move_player(gs, DL_DOWN + DL_LEFT),
+        Actions::MoveDownRight => try_move_player(gs, DL_DOWN + DL_RIGHT),
+
+        Actions::Wait => true,
+
+        Actions::CycleTarget => {
+            gs.player_targets.reset_targets(&gs.world.objects, &gs.world.map);
+            gs.player_targets.cycle_current_target();
+            false
+        },
+
+        Actions::UseAbility(slot) => {
+            if slot < gs.stored_abilities.len() {
+                handle_abilities(&mut gs.world.objects, &mut gs.world.map, &gs.world.data, &mut gs.stored_abilities[slot], &mut gs.world.rng.combat, &mut gs.events, gs.player_targets.get_current_target());
+            }
+            false
+        },
+
+        Actions::ShowHelp => {
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("=====================================================================================", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("Press", ColorPair::new(WHITE,GREY10))
+                .add_part("M", ColorPair::new(LIME_GREEN,GREY10))
+                .add_part("to read back through every message so far.", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("Press", ColorPair::new(WHITE,GREY10))
+                .add_part("F5", ColorPair::new(LIME_GREEN,GREY10))
+                .add_part("to save your journey, and", ColorPair::new(WHITE,GREY10))
+                .add_part("F9", ColorPair::new(LIME_GREEN,GREY10))
+                .add_part("to load it again.", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("You can also press", ColorPair::new(WHITE,GREY10))
+                .add_part("Control", ColorPair::new(YELLOW,GREY10))
+                .add_part("in conjunction with an ability key to see a description of what that ability does.", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("Press the corresponding", ColorPair::new(WHITE,GREY10))
+                .add_part("Numeric key", ColorPair::new(YELLOW,GREY10))
+                .add_part("to use abilities listed in the sidebar (plus Shift or Alt if there is an S next to the number).", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("Press", ColorPair::new(WHITE,GREY10))
+                .add_part("T", ColorPair::new(RED,GREY10))
+                .add_part("to cycle through visible targets.", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("Press", ColorPair::new(WHITE,GREY10))
+                .add_part("Numpad 5,", ColorPair::new(GREEN,GREY10))
+                .add_part("or .", ColorPair::new(CYAN,GREY10))
+                .add_part("to wait a turn.", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("Use the", ColorPair::new(WHITE,GREY10))
+                .add_part("Numpad (7,9,1,3),", ColorPair::new(GREEN,GREY10))
+                .add_part("or Y,U,B,N", ColorPair::new(CYAN,GREY10))
+                .add_part("to move diagonally.", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("Use the", ColorPair::new(WHITE,GREY10))
+                .add_part("arrow keys,", ColorPair::new(GOLD,GREY10))
+                .add_part("Numpad (8,2,4,6),", ColorPair::new(GREEN,GREY10))
+                .add_part("or Vim keys (J,K,H,L)", ColorPair::new(CYAN,GREY10))
+                .add_part("to move ↑, ↓, ←, and →.", ColorPair::new(WHITE,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("Controls:", ColorPair::new(GOLD,GREY10))
+            );
+            gs.logs.update_logs(LogMessage::new().category(LogCategory::Help)
+                .add_part("=====================================================================================", ColorPair::new(WHITE,GREY10))
+            );
+
+            false
+        }
+    };
+    gs.passed_turn = result;
+}
+
+fn try_move_player(gs: &mut Game, delta: Point) -> bool {
+    let map = &gs.world.map;
+    let camera = &mut gs.world.camera;
+    let player = &mut gs.world.objects[0];
+
+    let mut dest = player.pos.unwrap() + delta;
+
+    player.try_move(dest, map);
+    camera.move_camera(player.pos.unwrap());
+
+    return if player.pos.unwrap() == dest { true } else { try_attack_player(gs, &mut dest) }
+}
+
+//Attempts to attack something
+fn try_attack_player(gs: &mut Game, dest: &mut Point) -> bool {
+    let (player, all) = gs.world.objects.split_at_mut(1);
+    let mut target: Option<&mut Object> = None;
+
+    for obj in all.iter_mut() {
+        if let Object { pos: Some(pos), tag: tag, .. } = obj {
+            if pos == dest && obj.floor == player[0].floor && tag == &mut ActorTag::Enemy {
+                target = Some(obj);
+            }
+        }
+    }
+
+    return if let Some(tgt) = target {
+        player[0].try_attack(tgt, &mut gs.world.rng.combat, &mut gs.events);
+        true
+    } else {
+        false
+    }
+}
+
+/// Writes an ability's description to the log.
+pub fn describe_ability(gs: &mut Game, ability_idx: usize) {
+    if ability_idx < gs.stored_abilities.len() {
+        let ability = &gs.stored_abilities[ability_idx];
+
+        gs.logs.update_logs(LogMessage::new().category(LogCategory::Ability)
+            .add_part("-------------------------------------------------------------------------------------", ColorPair::new(WHITE, GREY10))
+        );
+        gs.logs.update_logs(LogMessage::new().category(LogCategory::Ability)
+            .add_part(gs.world.data.ability(&ability.name).map_or("", |a| a.description.as_str()), ColorPair::new(WHITE, GREY10))
+        );
+        gs.logs.update_logs(LogMessage::new().category(LogCategory::Ability)
+            .add_part(format! {"{}:", ability.name}, ColorPair::new(GOLD, GREY10))
+        );
+        gs.logs.update_logs(LogMessage::new().category(LogCategory::Ability)
+            .add_part("-------------------------------------------------------------------------------------", ColorPair::new(WHITE, GREY10))
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,164 @@
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Every floor generated so far and everything on them, plus the random streams that shape them.
+/// Only the floor the player is on gets simulated; the rest wait exactly as they were left.
+#[derive(Serialize, Deserialize)]
+pub struct World {
+    pub rng: RunRng,
+    pub entities: EntityAllocator,
+    pub objects: Vec<Object>,
+    pub map: Map,
+    pub depth: u32,
+    //The deepest floor reached so far
+    pub max_depth: u32,
+    //Every floor but the current one, by depth
+    pub stashed_maps: HashMap<u32, Map>,
+    pub camera: Camera,
+    //Comes from the data files rather than the save, so it gets handed back in when a game is loaded
+    #[serde(skip)]
+    pub data: Rc<GameData>
+}
+impl World {
+    /// Builds and populates the first floor of a new run.
+    pub fn new_game(seed: u64, data: Rc<GameData>) -> World {
+        let mut rng = RunRng::new(seed);
+        let map = build_floor(1, data.config.map_width, data.config.map_height, &data.biome(1).terrain, &data.prefabs, &mut rng.map);
+        let camera = Camera::new(map.starting_pos.clone());
+
+        let mut world = World {
+            rng,
+            entities: EntityAllocator::new(),
+            objects: Vec::new(),
+            map,
+            depth: 1,
+            max_depth: 1,
+            stashed_maps: HashMap::new(),
+            camera,
+            data
+        };
+
+        let player = spawn_player(&world.data, &mut world.rng, world.map.starting_pos.clone());
+        world.spawn(player);
+        world.populate_level();
+
+        return world
+    }
+
+    //Gives an object its entity ID and adds it to the world
+    pub fn spawn(&mut self, mut obj: Object) -> EntityId {
+        obj.id = self.entities.allocate();
+        let id = obj.id;
+        self.objects.push(obj);
+        return id
+    }
+    pub fn index_of(&self, id: EntityId) -> Option<usize> { self.objects.index_of(id) }
+    pub fn get_object(&self, id: EntityId) -> Option<&Object> { self.objects.get_by_id(id) }
+    pub fn get_object_mut(&mut self, id: EntityId) -> Option<&mut Object> { self.objects.get_by_id_mut(id) }
+    pub fn player_id(&self) -> EntityId { self.objects[0].id }
+
+    /// Moves the player to another floor, generating and populating it the first time it's reached.
+    /// The floor being left is stashed away along with everything on it.
+    pub fn change_floor(&mut self, depth: u32) {
+        let going_down = depth > self.depth;
+        let new_map = match self.stashed_maps.remove(&depth) {
+            Some(map) => map,
+            None => self.generate_floor(depth)
+        };
+        let old_map = std::mem::replace(&mut self.map, new_map);
+        self.stashed_maps.insert(self.depth, old_map);
+        self.depth = depth;
+
+        let first_visit = depth > self.max_depth;
+        if first_visit {
+            self.max_depth = depth;
+            self.populate_level();
+        }
+        update_blocked_tiles(&self.objects, &mut self.map, depth);
+
+        //Arrive next to the portal leading back the way we came, rather than on top of it
+        let portal = if going_down { self.map.starting_pos } else { self.map.exit_pos };
+        let arrival = self.arrival_point(portal);
+        self.camera = Camera::new(arrival);
+        self.objects[0].pos = Some(arrival);
+        self.objects[0].floor = depth;
+
+        //Everything here has been sitting still, so have them all take a fresh look around
+        for obj in self.objects.iter_mut().filter(|o| o.floor == depth) {
+            if let Some(view) = &mut obj.viewshed { view.refresh = true; }
+        }
+    }
+
+    //Builds a brand new floor for a depth, with a portal back up where the player arrives
+    fn generate_floor(&mut self, depth: u32) -> Map {
+        let mut map = build_floor(depth, self.data.config.map_width, self.data.config.map_height, &self.data.biome(depth).terrain, &self.data.prefabs, &mut self.rng.map);
+        let start_idx = map.point2d_to_index(map.starting_pos);
+        map.tiles[start_idx] = TileClass::ReturnPortal;
+        return map
+    }
+
+    //The closest open tile around a portal, searching further out if everything next to it is taken
+    fn arrival_point(&self, portal: Point) -> Point {
+        for radius in 1..=4 {
+            for y in -radius..=radius {
+                for x in -radius..=radius {
+                    let pos = portal + Point::new(x, y);
+                    if !self.map.in_bounds(pos) { continue }
+                    let idx = self.map.point2d_to_index(pos);
+                    if self.map.walkable(pos) && !self.map.tiles[idx].is_portal() {
+                        return pos
+                    }
+                }
+            }
+        }
+        return portal
+    }
+
+    //Fills the current map with whatever its prefabs and the spawn table for this depth call for
+    fn populate_level(&mut self) {
+        //Prefabs say exactly what goes where, so they get filled in before anything random
+        for (marker, pos) in std::mem::take(&mut self.map.spawn_markers) {
+            let obj = match marker {
+                SpawnMarker::Band => spawn_band_of_forsaken(&self.data, &mut self.rng, pos, self.depth),
+                SpawnMarker::Beast => spawn_beast(&self.data, &mut self.rng, pos, self.depth),
+                SpawnMarker::Elf => spawn_elf_pickup(&self.data, &mut self.rng, pos, self.depth),
+                //Nothing to put here until there are items
+                SpawnMarker::Item => continue
+            };
+            self.spawn(obj);
+        }
+
+        let (elves, beasts, bands) = {
+            let table = self.data.spawn_table(self.depth);
+            (table.elves, table.beasts, table.bands)
+        };
+
+        for _ in 1..=elves {
+            if let Some(pos) = self.take_spawn_point(0) {
+                let obj = spawn_elf_pickup(&self.data, &mut self.rng, pos, self.depth);
+                self.spawn(obj);
+            }
+        }
+        for _ in 1..=beasts {
+            if let Some(pos) = self.take_spawn_point(16) {
+                let obj = spawn_beast(&self.data, &mut self.rng, pos, self.depth);
+                self.spawn(obj);
+            }
+        }
+        for _ in 1..=bands {
+            if let Some(pos) = self.take_spawn_point(16) {
+                let obj = spawn_band_of_forsaken(&self.data, &mut self.rng, pos, self.depth);
+                self.spawn(obj);
+            }
+        }
+    }
+
+    //Picks a random free spawn point and uses it up, as long as more than `keep` of them are left
+    fn take_spawn_point(&mut self, keep: usize) -> Option<Point> {
+        if self.map.valid_spawns.len() <= keep + 1 { return None }
+        let max_roll = self.map.valid_spawns.len() - 1;
+        let index = self.rng.spawns.range(0, max_roll);
+        return Some(self.map.valid_spawns.remove(index))
+    }
+}
@@ -0,0 +1,58 @@
+//Floors bigger than the default have to play the same as small ones, enemies chasing the party included
+use elfball::prelude::*;
+use std::rc::Rc;
+
+const SIZE: i32 = 128;
+//The old fixed-size pathing maps only reached this far into the tiles
+const OLD_PATHING_TILES: usize = 90 * 90;
+//Enemies at the shallowest depths only act every other turn, so give them a few to close in
+const MAX_WAITS: u32 = 6;
+
+fn big_map_data() -> Rc<GameData> {
+    let mut data = GameData::load().expect("the data files in res/ should load");
+    data.config.map_width = SIZE;
+    data.config.map_height = SIZE;
+    return Rc::new(data)
+}
+
+//An enemy, and open ground it can see a few tiles away from it, past where the old pathing maps ended
+fn chase_setup(game: &Game) -> Option<(EntityId, Point)> {
+    let map = &game.world.map;
+    for obj in game.world.objects.iter() {
+        if let Object { id, pos: Some(pos), tag: ActorTag::Enemy, ai: Some(_), viewshed: Some(view), floor, .. } = obj {
+            if *floor != game.world.depth { continue }
+            let spot = view.visible.iter().find(|p| {
+                let distance = DistanceAlg::Pythagoras.distance2d(*pos, **p);
+                map.walkable(**p) && map.point2d_to_index(**p) >= OLD_PATHING_TILES && distance > 2.0 && distance <= 5.0
+            });
+            if let Some(spot) = spot { return Some((*id, *spot)) }
+        }
+    }
+    return None
+}
+
+#[test]
+fn enemies_chase_the_party_across_a_big_floor() {
+    let data = big_map_data();
+    let (mut game, enemy, spot) = (1..20)
+        .map(|seed| Game::new(seed, data.clone()))
+        .find_map(|game| chase_setup(&game).map(|(enemy, spot)| (game, enemy, spot)))
+        .expect("no seed put an enemy deep enough into a big floor");
+    assert_eq!(game.world.map.tiles.len(), (SIZE * SIZE) as usize);
+
+    //Drop the party right where the enemy is looking
+    game.world.objects[0].pos = Some(spot);
+    game.world.objects[0].viewshed.as_mut().unwrap().refresh = true;
+
+    let distance = |game: &Game| {
+        let idx = game.world.objects.index_of(enemy).expect("the enemy went missing");
+        DistanceAlg::Pythagoras.distance2d(game.world.objects[idx].pos.unwrap(), game.world.objects[0].pos.unwrap())
+    };
+    let start = distance(&game);
+
+    for _ in 0..MAX_WAITS {
+        game.step(Actions::Wait);
+        if distance(&game) < start { return }
+    }
+    panic!("the enemy never closed in on the party");
+}
@@ -0,0 +1,35 @@
+//Floors the party has left wait exactly as they were, so going back up finds everything where it was
+use elfball::prelude::*;
+use std::rc::Rc;
+
+//Who's on a floor, where, and how hurt they are
+fn enemies_on(world: &World, floor: u32) -> Vec<(EntityId, Option<Point>, Vec<i32>)> {
+    return world.objects.iter()
+        .filter(|o| o.floor == floor && o.tag == ActorTag::Enemy)
+        .map(|o| (o.id, o.pos, o.members.iter().map(|m| m.health.get_life()).collect()))
+        .collect()
+}
+
+#[test]
+fn returning_to_a_floor_finds_it_unchanged() {
+    let data = Rc::new(GameData::load().expect("the data files in res/ should load"));
+    let mut game = Game::new(5, data);
+
+    let enemies = enemies_on(&game.world, 1);
+    let revealed = game.world.map.revealed.clone();
+    let tiles = game.world.map.tiles.clone();
+    assert!(!enemies.is_empty(), "the first floor should have enemies on it");
+    assert!(revealed.iter().any(|r| *r), "the player should have seen some of the first floor");
+
+    game.world.change_floor(2);
+    assert_eq!(game.world.depth, 2);
+    assert_eq!(game.world.max_depth, 2);
+    assert!(!enemies_on(&game.world, 2).is_empty(), "the second floor should have been populated");
+
+    game.world.change_floor(1);
+    assert_eq!(game.world.depth, 1);
+    assert_eq!(game.world.max_depth, 2);
+    assert!(enemies_on(&game.world, 1) == enemies, "the enemies on the first floor moved or changed while it was stashed");
+    assert!(game.world.map.revealed == revealed, "what the player had seen of the first floor was lost");
+    assert!(game.world.map.tiles == tiles, "the first floor's ground changed while it was stashed");
+}
@@ -0,0 +1,129 @@
+//Every floor has to be walkable end to end: the portal out and anything that spawns must be reachable from
+//where the player starts. Checked with bracket-lib's own Dijkstra map rather than the flood fill the builders use.
+use elfball::prelude::*;
+use std::rc::Rc;
+
+const SEEDS: u64 = 200;
+
+fn reachable_from(map: &Map, start: Point) -> Vec<bool> {
+    let starts = vec![map.point2d_to_index(start)];
+    let dijkstra = DijkstraMap::new(map.width, map.height, &starts, map, 8192.0);
+    return dijkstra.map.iter().map(|d| *d < f32::MAX).collect()
+}
+
+fn assert_connected(map: &Map, what: &str) {
+    let reached = reachable_from(map, map.starting_pos);
+
+    assert!(reached[map.point2d_to_index(map.exit_pos)], "{}: the portal at {:?} can't be reached from {:?}", what, map.exit_pos, map.starting_pos);
+    for spawn in map.valid_spawns.iter() {
+        assert!(reached[map.point2d_to_index(*spawn)], "{}: the spawn point {:?} can't be reached", what, spawn);
+    }
+    for (idx, tile) in map.tiles.iter().enumerate() {
+        if !tile.does_collide() {
+            assert!(reached[idx], "{}: open ground at {:?} was left cut off", what, map.index_to_point2d(idx));
+        }
+    }
+}
+
+fn check_builder<B: MapBuilder>(name: &str, make: fn() -> B) {
+    for seed in 0..SEEDS {
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        let map = make().build(64, 64, &mut rng);
+        assert_ne!(map.starting_pos, map.exit_pos, "{} seed {}: the portal was put on the start", name, seed);
+        assert_connected(&map, &format!("{} seed {}", name, seed));
+    }
+}
+
+#[test]
+fn cellular_floors_are_connected() {
+    check_builder("cellular", || CellularAutomataBuilder::new(true));
+    check_builder("cellular (start at the edge)", || CellularAutomataBuilder::new(false));
+}
+
+#[test]
+fn drunkard_floors_are_connected() {
+    check_builder("drunkard", DrunkardsWalkBuilder::new);
+}
+
+#[test]
+fn voronoi_floors_are_connected() {
+    check_builder("voronoi", VoronoiGroveBuilder::new);
+}
+
+#[test]
+fn bsp_ruin_floors_are_connected() {
+    check_builder("bsp ruins", BspRuinsBuilder::new);
+}
+
+#[test]
+fn small_maps_are_connected() {
+    for seed in 0..SEEDS {
+        for depth in 1..=8 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let map = build_floor(depth, 24, 20, &TerrainMix::default(), &[], &mut rng);
+            assert_connected(&map, &format!("24x20 depth {} seed {}", depth, seed));
+        }
+    }
+}
+
+//Every biome's terrain mix, on each of the generators it could be grown with
+#[test]
+fn biome_floors_are_connected() {
+    let data = GameData::load().expect("the data files in res/ should load");
+    for biome in data.biomes.iter() {
+        for seed in 0..SEEDS {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let maps = vec![
+                ("cellular", CellularAutomataBuilder::new(true).with_terrain(biome.terrain.clone()).build(64, 64, &mut rng)),
+                ("drunkard", DrunkardsWalkBuilder::new().with_terrain(biome.terrain.clone()).build(64, 64, &mut rng)),
+                ("voronoi", VoronoiGroveBuilder::new().with_terrain(biome.terrain.clone()).build(64, 64, &mut rng)),
+                ("bsp ruins", BspRuinsBuilder::new().with_terrain(biome.terrain.clone()).build(64, 64, &mut rng))
+            ];
+            for (name, map) in maps.iter() {
+                assert_connected(map, &format!("{} {} seed {}", biome.name, name, seed));
+            }
+        }
+    }
+}
+
+//Every prefab forced onto every floor it can appear on, as often as it'll fit
+#[test]
+fn prefabs_keep_floors_connected() {
+    let data = GameData::load().expect("the data files in res/ should load");
+    let prefabs: Vec<PrefabDef> = data.prefabs.iter().map(|p| PrefabDef { chance: 100, ..p.clone() }).collect();
+    for seed in 0..SEEDS {
+        for depth in 1..=8 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let map = build_floor(depth, 64, 64, &data.biome(depth).terrain, &prefabs, &mut rng);
+            let what = format!("prefabs depth {} seed {}", depth, seed);
+            assert_connected(&map, &what);
+
+            let reached = reachable_from(&map, map.starting_pos);
+            for (_, pos) in map.spawn_markers.iter() {
+                assert!(reached[map.point2d_to_index(*pos)], "{}: a prefab spawn marker at {:?} can't be reached", what, pos);
+            }
+        }
+    }
+}
+
+//Goes down through a run's floors and checks everything that was placed on each one can be walked to
+#[test]
+fn everything_spawned_can_be_reached() {
+    let data = Rc::new(GameData::load().expect("the data files in res/ should load"));
+    for seed in 0..20 {
+        let mut world = World::new_game(seed, data.clone());
+        for depth in 1..=6 {
+            if depth > 1 { world.change_floor(depth); }
+            //Only the ground matters here, not who happens to be standing on it
+            for b in world.map.obj_blocked.iter_mut() { *b = false; }
+            let player_pos = world.objects[0].pos.unwrap();
+            let reached = reachable_from(&world.map, player_pos);
+
+            for obj in world.objects.iter().filter(|o| o.floor == depth) {
+                let pos = obj.pos.unwrap();
+                assert!(reached[world.map.point2d_to_index(pos)], "seed {} depth {}: {} at {:?} can't be reached", seed, depth, obj.name, pos);
+            }
+            assert!(reached[world.map.point2d_to_index(world.map.exit_pos)], "seed {} depth {}: the portal can't be reached", seed, depth);
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//The same seed and the same actions have to give the same run, and a recording that's been tampered with has to
+//be caught at the turn it stops matching
+use elfball::prelude::*;
+use std::rc::Rc;
+
+const SEED: u64 = 7;
+const STEPS: u32 = 300;
+
+fn record_run(data: &Rc<GameData>) -> Replay {
+    let mut game = Game::new(SEED, data.clone());
+    run_agent(&mut game, &mut GreedyAgent, STEPS);
+    return game.replay.clone()
+}
+
+#[test]
+fn recorded_run_plays_back_the_same() {
+    let data = Rc::new(GameData::load().expect("the data files in res/ should load"));
+    let replay = record_run(&data);
+    assert!(!replay.turns.is_empty(), "the agent didn't take any actions");
+
+    if let Err(desync) = replay.verify(data) {
+        panic!("desync on turn {}: expected {:016x}, found {:016x}", desync.turn, desync.expected, desync.found);
+    }
+}
+
+#[test]
+fn edited_action_is_caught_where_it_was_changed() {
+    let data = Rc::new(GameData::load().expect("the data files in res/ should load"));
+    let mut replay = record_run(&data);
+
+    //Standing still instead of taking a step leaves the party somewhere else
+    let edited = replay.turns.iter().position(|t| t.action != Actions::Wait).expect("the agent never moved");
+    replay.turns[edited].action = Actions::Wait;
+
+    match replay.verify(data) {
+        Ok(_) => panic!("the edit on turn {} went unnoticed", edited),
+        Err(desync) => assert_eq!(desync.turn, edited)
+    }
+}
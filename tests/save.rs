@@ -0,0 +1,53 @@
+//A saved game has to come back exactly as it was left, and a save from another version of the format has to be
+//turned away cleanly rather than misread
+use elfball::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+fn temp_path(name: &str) -> PathBuf {
+    return std::env::temp_dir().join(format!("elfball-{}-{}.json", name, std::process::id()))
+}
+
+#[test]
+fn saved_game_loads_back_the_same() {
+    let data = Rc::new(GameData::load().expect("the data files in res/ should load"));
+    let mut game = Game::new(11, data.clone());
+    run_agent(&mut game, &mut GreedyAgent, 150);
+
+    let path = temp_path("round-trip");
+    if let Err(e) = game.save_to_disk(&path) { panic!("could not save: {}", e) }
+    let loaded = Game::load_from_disk(&path, data);
+    let _ = fs::remove_file(&path);
+    let mut loaded = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => panic!("could not load: {}", e)
+    };
+    assert_eq!(loaded.state_hash(), game.state_hash());
+
+    //Everything that drives what happens next, the random streams included, has to have come back too
+    for step in 0..50 {
+        let action = GreedyAgent.act(&game.world, &game.player_targets);
+        game.step(action);
+        loaded.step(action);
+        assert_eq!(loaded.state_hash(), game.state_hash(), "the loaded game drifted {} steps after loading", step + 1);
+    }
+}
+
+#[test]
+fn save_from_another_version_is_turned_away() {
+    let data = Rc::new(GameData::load().expect("the data files in res/ should load"));
+    let path = temp_path("wrong-version");
+    fs::write(&path, format!("{{\"version\": {}, \"game\": {{}}}}", SAVE_VERSION + 1)).unwrap();
+
+    let result = Game::load_from_disk(&path, data);
+    let _ = fs::remove_file(&path);
+    match result {
+        Err(SaveError::WrongVersion { found, expected }) => {
+            assert_eq!(found, SAVE_VERSION + 1);
+            assert_eq!(expected, SAVE_VERSION);
+        }
+        Err(e) => panic!("expected a version mismatch, got: {}", e),
+        Ok(_) => panic!("a save from another version was loaded")
+    }
+}